@@ -0,0 +1,59 @@
+//! Lexes 4 MiB of generated source. Median throughput, on the same machine:
+//!
+//! | commit                             | tokens     | lossless   |
+//! |------------------------------------|------------|------------|
+//! | 3074b56, tokens own a `String`     | 32.1 MiB/s | 29.8 MiB/s |
+//! | d6856d4, tokens borrow the source  | 105 MiB/s  | 93.4 MiB/s |
+//!
+//! The owned run made `TokenIterator` public so this file could build against it; d6856d4 also
+//! has the lexer features added after 3074b56.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use kial_compiler::lexer::TokenIterator;
+
+const SNIPPET: &str = r##"/// Adds things up
+func sum_{n}(a: i32, b: i32) -> i32 {
+    let mut total = a + b * 0x1f - 0b1010; // mixed radixes
+    /* a block comment /* nested */ */
+    while total >= 1_000 && !done {
+        total -= 2.5e3;
+    }
+    let greeting = "Hello, \"world\"\n";
+    let raw = r#"no \escapes here"#;
+    return total;
+}
+
+"##;
+
+/// Roughly `size` bytes of Kial source, made up of many small functions
+fn generate_source(size: usize) -> String {
+    let mut src = String::with_capacity(size + SNIPPET.len());
+    let mut n = 0;
+    while src.len() < size {
+        src.push_str(&SNIPPET.replace("{n}", &n.to_string()));
+        n += 1;
+    }
+
+    src
+}
+
+fn lex(c: &mut Criterion) {
+    let src = generate_source(4 * 1024 * 1024);
+
+    let mut group = c.benchmark_group("lex_4mb");
+    group.throughput(Throughput::Bytes(src.len() as u64));
+    group.sample_size(20);
+
+    group.bench_function("tokens", |b| {
+        b.iter(|| TokenIterator::from(black_box(src.as_str())).count())
+    });
+
+    group.bench_function("lossless", |b| {
+        b.iter(|| TokenIterator::lossless(black_box(src.as_str())).count())
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, lex);
+criterion_main!(benches);
@@ -1,15 +1,345 @@
-use crate::tokenstream::TokenStream;
+use crate::lexer::TokenKind;
+use crate::val::Val;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub(crate) enum Associativity {
+    Left,  // a op b op c is (a op b) op c
+    Right, // a op b op c is a op (b op c)
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
 pub(crate) enum BinOp {
     Add,
     Sub,
     Mul,
     Div,
+    Rem,
+    Pow,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
 }
 
+/// Every binary operator, with its precedence (higher binds tighter) and associativity
+#[rustfmt::skip]
+const OPERATORS: [(BinOp, TokenKind, u8, Associativity); 19] = [
+    (BinOp::Or,     TokenKind::PipePipe,       1,  Associativity::Left),
+    (BinOp::And,    TokenKind::AmpAmp,         2,  Associativity::Left),
+    (BinOp::Eq,     TokenKind::EqualsEquals,   3,  Associativity::Left),
+    (BinOp::Ne,     TokenKind::BangEquals,     3,  Associativity::Left),
+    (BinOp::Lt,     TokenKind::Less,           3,  Associativity::Left),
+    (BinOp::Le,     TokenKind::LessEquals,     3,  Associativity::Left),
+    (BinOp::Gt,     TokenKind::Greater,        3,  Associativity::Left),
+    (BinOp::Ge,     TokenKind::GreaterEquals,  3,  Associativity::Left),
+    (BinOp::BitOr,  TokenKind::Pipe,           4,  Associativity::Left),
+    (BinOp::BitXor, TokenKind::Caret,          5,  Associativity::Left),
+    (BinOp::BitAnd, TokenKind::Amp,            6,  Associativity::Left),
+    (BinOp::Shl,    TokenKind::LessLess,       7,  Associativity::Left),
+    (BinOp::Shr,    TokenKind::GreaterGreater, 7,  Associativity::Left),
+    (BinOp::Add,    TokenKind::Plus,           8,  Associativity::Left),
+    (BinOp::Sub,    TokenKind::Minus,          8,  Associativity::Left),
+    (BinOp::Mul,    TokenKind::Star,           9,  Associativity::Left),
+    (BinOp::Div,    TokenKind::Slash,          9,  Associativity::Left),
+    (BinOp::Rem,    TokenKind::Percent,        9,  Associativity::Left),
+    (BinOp::Pow,    TokenKind::StarStar,       10, Associativity::Right),
+];
+
 impl BinOp {
-    fn parse(ts: TokenStream) -> Result<Self, String> {
-        todo!()
+    fn entry(&self) -> (BinOp, TokenKind, u8, Associativity) {
+        *OPERATORS.iter().find(|(op, ..)| op == self).unwrap() // Every BinOp is in the table
+    }
+
+    pub(crate) fn precedence(&self) -> u8 {
+        self.entry().2
+    }
+
+    pub(crate) fn associativity(&self) -> Associativity {
+        self.entry().3
+    }
+
+    /// `&&` and `||`, which only evaluate their right-hand side when the left doesn't decide
+    /// the result
+    pub(crate) fn is_short_circuit(&self) -> bool {
+        matches!(self, BinOp::And | BinOp::Or)
+    }
+
+    /// The result of `&&` or `||` when `lhs` alone decides it
+    pub(crate) fn short_circuit(&self, lhs: &Val) -> Option<Val> {
+        match (self, lhs) {
+            (BinOp::And, Val::Bool(false)) | (BinOp::Or, Val::Bool(true)) => Some(lhs.clone()),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn eval(&self, lhs: Val, rhs: Val) -> Result<Val, String> {
+        let unsupported =
+            || format!("Unsupported operation, lhs: {lhs:?} | rhs: {rhs:?} | op: {self:?}");
+
+        // Only values of the same type can be compared
+        let comparable = std::mem::discriminant(&lhs) == std::mem::discriminant(&rhs);
+        let comparison = match self {
+            BinOp::Eq => Some(lhs == rhs),
+            BinOp::Ne => Some(lhs != rhs),
+            BinOp::Lt => Some(lhs < rhs),
+            BinOp::Le => Some(lhs <= rhs),
+            BinOp::Gt => Some(lhs > rhs),
+            BinOp::Ge => Some(lhs >= rhs),
+            _ => None,
+        };
+        if let Some(result) = comparison {
+            return comparable
+                .then_some(Val::Bool(result))
+                .ok_or_else(unsupported);
+        }
+
+        match (&lhs, &rhs) {
+            (Val::Int(l), Val::Int(r)) => {
+                let result = match self {
+                    BinOp::Add => l.checked_add(*r),
+                    BinOp::Sub => l.checked_sub(*r),
+                    BinOp::Mul => l.checked_mul(*r),
+                    BinOp::Div if *r == 0 => return Err("Division by zero".to_string()),
+                    BinOp::Div => l.checked_div(*r),
+                    BinOp::Rem if *r == 0 => return Err("Modulo by zero".to_string()),
+                    BinOp::Rem => l.checked_rem(*r),
+                    BinOp::Pow => {
+                        let exponent = u32::try_from(*r).map_err(|_| {
+                            format!(
+                                "Integer exponent must be between 0 and {}, got {r}",
+                                u32::MAX
+                            )
+                        })?;
+                        l.checked_pow(exponent)
+                    }
+                    BinOp::BitAnd => Some(l & r),
+                    BinOp::BitOr => Some(l | r),
+                    BinOp::BitXor => Some(l ^ r),
+                    BinOp::Shl | BinOp::Shr if *r < 0 => {
+                        return Err(format!("Negative shift amount in {l} {self:?} {r}"))
+                    }
+                    BinOp::Shl => u32::try_from(*r).ok().and_then(|r| l.checked_shl(r)),
+                    BinOp::Shr => u32::try_from(*r).ok().and_then(|r| l.checked_shr(r)),
+                    _ => return Err(unsupported()),
+                };
+
+                result
+                    .map(Val::Int)
+                    .ok_or_else(|| format!("Integer overflow in {l} {self:?} {r}"))
+            }
+            (Val::Float(l), Val::Float(r)) => Ok(Val::Float(match self {
+                BinOp::Add => l + r,
+                BinOp::Sub => l - r,
+                BinOp::Mul => l * r,
+                BinOp::Div => l / r,
+                BinOp::Rem if *r == 0.0 => return Err("Modulo by zero".to_string()),
+                BinOp::Rem => l % r,
+                BinOp::Pow => l.powf(*r),
+                _ => return Err(unsupported()),
+            })),
+            (Val::Float(l), Val::Int(r)) => match self {
+                BinOp::Pow => Ok(Val::Float(l.powf(*r as f64))),
+                _ => Err(unsupported()),
+            },
+            (Val::Bool(l), Val::Bool(r)) => match self {
+                BinOp::And => Ok(Val::Bool(*l && *r)),
+                BinOp::Or => Ok(Val::Bool(*l || *r)),
+                _ => Err(unsupported()),
+            },
+            (Val::Str(l), Val::Str(r)) => match self {
+                BinOp::Add => Ok(Val::Str(format!("{l}{r}"))),
+                _ => Err(unsupported()),
+            },
+            _ => Err(unsupported()),
+        }
+    }
+}
+
+impl TryFrom<TokenKind> for BinOp {
+    type Error = String;
+
+    fn try_from(kind: TokenKind) -> Result<Self, Self::Error> {
+        OPERATORS
+            .iter()
+            .find(|(_, token_kind, ..)| *token_kind == kind)
+            .map(|(op, ..)| *op)
+            .ok_or_else(|| format!("\"{kind:?}\" is not a binary operator"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::binary_operation::{Associativity, BinOp, OPERATORS};
+    use crate::lexer::TokenKind;
+    use crate::val::Val;
+
+    #[test]
+    fn operator_table() {
+        assert_eq!(BinOp::try_from(TokenKind::Star), Ok(BinOp::Mul));
+        assert_eq!(
+            BinOp::try_from(TokenKind::Semi),
+            Err(r#""Semi" is not a binary operator"#.to_string())
+        );
+        assert!(BinOp::Mul.precedence() > BinOp::Add.precedence());
+        assert_eq!(BinOp::Sub.associativity(), Associativity::Left);
+        assert_eq!(BinOp::Pow.associativity(), Associativity::Right);
+        assert!(BinOp::Pow.precedence() > BinOp::Mul.precedence());
+        assert!(BinOp::Add.precedence() > BinOp::Shl.precedence());
+        assert!(BinOp::Shl.precedence() > BinOp::BitAnd.precedence());
+        assert!(BinOp::BitAnd.precedence() > BinOp::BitXor.precedence());
+        assert!(BinOp::BitXor.precedence() > BinOp::BitOr.precedence());
+        assert!(BinOp::BitOr.precedence() > BinOp::Eq.precedence());
+        assert!(BinOp::Eq.precedence() > BinOp::And.precedence());
+        assert!(BinOp::And.precedence() > BinOp::Or.precedence());
+
+        for (op, kind, ..) in OPERATORS {
+            assert_eq!(BinOp::try_from(kind), Ok(op));
+        }
+    }
+
+    #[test]
+    fn eval_remainder_and_power() {
+        assert_eq!(BinOp::Rem.eval(Val::Int(-7), Val::Int(3)), Ok(Val::Int(-1)));
+        assert_eq!(
+            BinOp::Rem.eval(Val::Float(7.5), Val::Float(2.0)),
+            Ok(Val::Float(1.5))
+        );
+        assert_eq!(
+            BinOp::Rem.eval(Val::Int(1), Val::Int(0)),
+            Err("Modulo by zero".to_string())
+        );
+
+        assert_eq!(
+            BinOp::Pow.eval(Val::Int(2), Val::Int(10)),
+            Ok(Val::Int(1024))
+        );
+        assert_eq!(BinOp::Pow.eval(Val::Int(-3), Val::Int(0)), Ok(Val::Int(1)));
+        assert_eq!(
+            BinOp::Pow.eval(Val::Float(4.0), Val::Float(0.5)),
+            Ok(Val::Float(2.0))
+        );
+        assert_eq!(
+            BinOp::Pow.eval(Val::Float(2.0), Val::Int(-1)),
+            Ok(Val::Float(0.5))
+        );
+        assert_eq!(
+            BinOp::Pow.eval(Val::Int(2), Val::Int(-1)),
+            Err("Integer exponent must be between 0 and 4294967295, got -1".to_string())
+        );
+        assert_eq!(
+            BinOp::Pow.eval(Val::Int(2), Val::Int(63)),
+            Err("Integer overflow in 2 Pow 63".to_string())
+        );
+    }
+
+    #[test]
+    fn eval_bitwise_and_shifts() {
+        assert_eq!(
+            BinOp::BitAnd.eval(Val::Int(0b1100), Val::Int(0b1010)),
+            Ok(Val::Int(0b1000))
+        );
+        assert_eq!(
+            BinOp::BitOr.eval(Val::Int(0b1100), Val::Int(0b1010)),
+            Ok(Val::Int(0b1110))
+        );
+        assert_eq!(
+            BinOp::BitXor.eval(Val::Int(0b1100), Val::Int(0b1010)),
+            Ok(Val::Int(0b0110))
+        );
+        assert_eq!(
+            BinOp::Shl.eval(Val::Int(1), Val::Int(62)),
+            Ok(Val::Int(1 << 62))
+        );
+        assert_eq!(
+            BinOp::Shr.eval(Val::Int(-16), Val::Int(2)),
+            Ok(Val::Int(-4))
+        );
+        assert_eq!(
+            BinOp::Shl.eval(Val::Int(1), Val::Int(-1)),
+            Err("Negative shift amount in 1 Shl -1".to_string())
+        );
+        assert_eq!(
+            BinOp::Shr.eval(Val::Int(1), Val::Int(64)),
+            Err("Integer overflow in 1 Shr 64".to_string())
+        );
+        assert_eq!(
+            BinOp::BitAnd.eval(Val::Float(1.0), Val::Float(1.0)),
+            Err(
+                "Unsupported operation, lhs: Float(1.0) | rhs: Float(1.0) | op: BitAnd".to_string()
+            )
+        );
+        assert_eq!(
+            BinOp::Rem.eval(Val::Float(1.0), Val::Float(0.0)),
+            Err("Modulo by zero".to_string())
+        );
+    }
+
+    #[test]
+    fn eval_comparisons() {
+        assert_eq!(
+            BinOp::Lt.eval(Val::Int(1), Val::Int(2)),
+            Ok(Val::Bool(true))
+        );
+        assert_eq!(
+            BinOp::Ge.eval(Val::Float(1.5), Val::Float(2.0)),
+            Ok(Val::Bool(false))
+        );
+        assert_eq!(
+            BinOp::Lt.eval(Val::Str("abc".to_string()), Val::Str("abd".to_string())),
+            Ok(Val::Bool(true))
+        );
+        assert_eq!(
+            BinOp::Eq.eval(Val::Char('a'), Val::Char('a')),
+            Ok(Val::Bool(true))
+        );
+        assert_eq!(
+            BinOp::Ne.eval(Val::Bool(true), Val::Bool(false)),
+            Ok(Val::Bool(true))
+        );
+        assert_eq!(BinOp::Eq.eval(Val::Unit, Val::Unit), Ok(Val::Bool(true)));
+        assert_eq!(
+            BinOp::Eq.eval(Val::Float(f64::NAN), Val::Float(f64::NAN)),
+            Ok(Val::Bool(false))
+        );
+        assert_eq!(
+            BinOp::Eq.eval(Val::Int(1), Val::Float(1.0)),
+            Err("Unsupported operation, lhs: Int(1) | rhs: Float(1.0) | op: Eq".to_string())
+        );
+    }
+
+    #[test]
+    fn eval_logical() {
+        assert_eq!(
+            BinOp::And.eval(Val::Bool(true), Val::Bool(false)),
+            Ok(Val::Bool(false))
+        );
+        assert_eq!(
+            BinOp::Or.eval(Val::Bool(false), Val::Bool(true)),
+            Ok(Val::Bool(true))
+        );
+        assert_eq!(
+            BinOp::And.eval(Val::Int(1), Val::Bool(true)),
+            Err("Unsupported operation, lhs: Int(1) | rhs: Bool(true) | op: And".to_string())
+        );
+
+        assert_eq!(
+            BinOp::And.short_circuit(&Val::Bool(false)),
+            Some(Val::Bool(false))
+        );
+        assert_eq!(BinOp::And.short_circuit(&Val::Bool(true)), None);
+        assert_eq!(
+            BinOp::Or.short_circuit(&Val::Bool(true)),
+            Some(Val::Bool(true))
+        );
+        assert_eq!(BinOp::Or.short_circuit(&Val::Int(1)), None);
     }
 }
@@ -1,24 +1,60 @@
 use crate::ast::statement::Stmt;
+use crate::env::Env;
 use crate::lexer::TokenKind;
+use crate::pear::error::{ParseError, ParseErrorKind};
 use crate::pear::Pear;
+use crate::trace;
+use crate::val::Val;
 
 #[derive(Debug, PartialEq)]
 pub(crate) struct Block {
     stmts: Vec<Stmt>,
 }
 
+impl Block {
+    /// Evaluates to the value of the last statement, with bindings scoped to the block
+    pub(crate) fn eval(&self, env: &Env) -> Result<Val, String> {
+        let mut env = env.create_child();
+        let mut last = Val::Unit;
+        for stmt in &self.stmts {
+            last = stmt.eval(&mut env)?;
+        }
+
+        Ok(last)
+    }
+}
+
 impl TryFrom<&mut Pear<'_>> for Block {
-    type Error = String;
+    type Error = ParseError;
 
     fn try_from(pear: &mut Pear) -> Result<Self, Self::Error> {
+        let _trace = trace::enter("Block", pear);
         let mut stmts = vec![];
 
-        pear.tag(TokenKind::OpenBrace)?;
-        while let Ok(stmt) = Stmt::try_from(&mut *pear) {
-            stmts.push(stmt);
+        let open = pear.tag(TokenKind::OpenBrace)?;
+        while let Some(next) = pear.peek_next() {
+            // Functions don't nest, so "func" means this block was never closed
+            if matches!(next.kind, TokenKind::CloseBrace | TokenKind::Func) {
+                break;
+            }
+
+            // An empty statement
+            if next.kind == TokenKind::Semi {
+                pear.tag(TokenKind::Semi)?;
+                continue;
+            }
+
+            match Stmt::try_from(&mut *pear) {
+                Ok(stmt) => stmts.push(stmt),
+                Err(error) => stmts.push(Stmt::Error(pear.recover(error))),
+            }
         }
 
-        pear.tag(TokenKind::CloseBrace)?;
+        pear.tag(TokenKind::CloseBrace).map_err(|e| ParseError {
+            kind: ParseErrorKind::Unclosed,
+            span: open.span,
+            ..e
+        })?;
 
         Ok(Self { stmts })
     }
@@ -27,7 +63,12 @@ impl TryFrom<&mut Pear<'_>> for Block {
 #[cfg(test)]
 mod tests {
     use crate::ast::block::Block;
+    use crate::ast::statement::Stmt;
+    use crate::env::Env;
+    use crate::lexer::Span;
+    use crate::pear::error::ParseErrorKind;
     use crate::pear::Pear;
+    use crate::val::Val;
 
     #[test]
     fn basic_block_test() {
@@ -42,4 +83,89 @@ a
         let block = Block::try_from(&mut pear);
         println!("{block:#?}")
     }
+
+    #[test]
+    fn eval_block() {
+        let mut env = Env::default();
+        env.store_binding("outer".to_string(), Val::Int(1));
+
+        let mut pear = Pear::from("{ let a = 10; a = outer; a }");
+        let block = Block::try_from(&mut pear).unwrap();
+        assert_eq!(block.eval(&env), Ok(Val::Int(1)));
+
+        let mut pear = Pear::from("{ let a = 10; }");
+        let block = Block::try_from(&mut pear).unwrap();
+        assert_eq!(block.eval(&env), Ok(Val::Unit));
+
+        let mut pear = Pear::from("{ b = 10; }");
+        let block = Block::try_from(&mut pear).unwrap();
+        assert_eq!(
+            block.eval(&env),
+            Err("Binding does not exist: b".to_string())
+        );
+    }
+
+    #[test]
+    fn assign_to_outer_bindings() {
+        let mut env = Env::default();
+        env.store_binding("a".to_string(), Val::Int(1));
+
+        let mut pear = Pear::from("{ let b = 2; { a = 10; b = a + b; }; b }");
+        let block = Block::try_from(&mut pear).unwrap();
+        assert_eq!(block.eval(&env), Ok(Val::Int(12)));
+        assert_eq!(env.get_binding("a"), Ok(Val::Int(10)));
+
+        // A binding in the inner block shadows the outer one, so only it is assigned to
+        let mut pear = Pear::from("{ let a = 2; a = 3; }");
+        let block = Block::try_from(&mut pear).unwrap();
+        assert_eq!(block.eval(&env), Ok(Val::Unit));
+        assert_eq!(env.get_binding("a"), Ok(Val::Int(10)));
+    }
+
+    #[test]
+    fn eval_expression_statements() {
+        let mut env = Env::default();
+        env.store_binding("a".to_string(), Val::Int(1));
+
+        let mut pear = Pear::from("{ a + 1; }");
+        let block = Block::try_from(&mut pear).unwrap();
+        assert!(matches!(block.stmts[..], [Stmt::Semi(_)]));
+        assert_eq!(block.eval(&env), Ok(Val::Unit));
+
+        let mut pear = Pear::from("{ a + 1;; a * 2 }");
+        let block = Block::try_from(&mut pear).unwrap();
+        assert!(matches!(block.stmts[..], [Stmt::Semi(_), Stmt::Expr(_)]));
+        assert_eq!(block.eval(&env), Ok(Val::Int(2)));
+        assert_eq!(pear.take_diagnostics(), vec![]);
+    }
+
+    #[test]
+    fn recover_from_bad_statements() {
+        let mut pear = Pear::from("{ let a = ; let b = 2; c = 1 + ; b }");
+        let block = Block::try_from(&mut pear).unwrap();
+
+        assert_eq!(block.stmts.len(), 4);
+        assert_eq!(block.stmts[0], Stmt::Error(Span::new(10, 11, 1, 11)));
+        assert!(matches!(block.stmts[1], Stmt::Binding(_)));
+        assert_eq!(block.stmts[2], Stmt::Error(Span::new(31, 32, 1, 32)));
+        assert!(matches!(block.stmts[3], Stmt::Expr(_)));
+        assert!(block.eval(&Env::default()).is_err());
+
+        let diagnostics = pear.take_diagnostics();
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics
+            .iter()
+            .all(|error| error.kind == ParseErrorKind::MissingExpression));
+    }
+
+    #[test]
+    fn recover_by_skipping_nested_blocks() {
+        let mut pear = Pear::from("{ let a 1 { ; } ; a }");
+        let block = Block::try_from(&mut pear).unwrap();
+
+        assert_eq!(block.stmts.len(), 2);
+        assert_eq!(block.stmts[0], Stmt::Error(Span::new(8, 17, 1, 9)));
+        assert_eq!(pear.take_diagnostics().len(), 1);
+        assert_eq!(pear.peek_next(), None);
+    }
 }
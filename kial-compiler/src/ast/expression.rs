@@ -1,39 +1,149 @@
-use crate::ast::binary_operation::BinOp;
+use crate::ast::binary_operation::{Associativity, BinOp};
 use crate::ast::block::Block;
 use crate::ast::function::FunctionInvocation;
-use crate::ast::literal::Literal;
+use crate::ast::interpolation::Interpolation;
+use crate::ast::literal::{IntLiteral, Literal};
 use crate::ast::statement::binding::BindingUsage;
+use crate::ast::unary_operation::UnaryOp;
+use crate::env::Env;
 use crate::lexer::TokenKind;
+use crate::pear::error::{ParseError, ParseErrorKind};
 use crate::pear::Pear;
+use crate::trace;
+use crate::val::Val;
 
 #[derive(Debug, PartialEq)]
 pub(crate) enum Expr {
     Binary(BinOp, Box<Expr>, Box<Expr>),
+    Unary(UnaryOp, Box<Expr>),
     Block(Block),
     Literal(Literal),
+    Interpolation(Interpolation),
     BindingUsage(BindingUsage),
     FunctionInvocation(FunctionInvocation),
 }
 
-impl TryFrom<&mut Pear<'_>> for Expr {
-    type Error = String;
+impl Expr {
+    pub(crate) fn eval(&self, env: &Env) -> Result<Val, String> {
+        match self {
+            Self::Binary(op, lhs, rhs) if op.is_short_circuit() => {
+                let lhs = lhs.eval(env)?;
+                match op.short_circuit(&lhs) {
+                    Some(result) => Ok(result),
+                    None => op.eval(lhs, rhs.eval(env)?),
+                }
+            }
+            Self::Binary(op, lhs, rhs) => op.eval(lhs.eval(env)?, rhs.eval(env)?),
+            Self::Unary(op, operand) => op.eval(operand.eval(env)?),
+            Self::Block(block) => block.eval(env),
+            Self::Literal(literal) => Ok(literal.eval()),
+            Self::Interpolation(interpolation) => interpolation.eval(env),
+            Self::BindingUsage(binding_usage) => binding_usage.eval(env),
+            Self::FunctionInvocation(func) => func.eval(env),
+        }
+    }
+}
 
-    fn try_from(pear: &mut Pear) -> Result<Self, Self::Error> {
+impl Expr {
+    /// Precedence climbing: parses operands and any operators binding at least as tightly as
+    /// `min_precedence`, so that "1 + 2 * 3" nests as 1 + (2 * 3)
+    fn parse_binary(pear: &mut Pear, min_precedence: u8) -> Result<Self, ParseError> {
+        let _trace = trace::enter("Expr", pear);
+        let mut lhs = Self::parse_primary(pear)?;
+
+        while let Some(token) = pear.peek_next() {
+            let Ok(op) = BinOp::try_from(token.kind) else {
+                break;
+            };
+
+            let precedence = op.precedence();
+            if precedence < min_precedence {
+                break;
+            }
+
+            pear.tag(token.kind)?;
+
+            let next_min_precedence = match op.associativity() {
+                Associativity::Left => precedence + 1,
+                Associativity::Right => precedence,
+            };
+            let rhs = Self::parse_binary(pear, next_min_precedence)?;
+            lhs = Self::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+
+        Ok(lhs)
+    }
+
+    /// An operand of a binary expression, along with any prefix operators in front of it
+    fn parse_primary(pear: &mut Pear) -> Result<Self, ParseError> {
         let Some(next) = pear.peek_next() else {
-            return Err("TODO: No tokens found".to_string()); // TODO
+            return Err(pear.error(ParseErrorKind::MissingExpression));
         };
 
-        // TODO: Reverse Polish Notation here?
+        if let Ok(op) = UnaryOp::try_from(next.kind) {
+            let _trace = trace::enter("Unary", pear);
+            pear.tag(next.kind)?;
+
+            // "-" directly before an integer literal is folded into a negative literal on purpose.
+            // It's the only way to write i64::MIN, whose magnitude is out of range for a positive
+            // literal. "**" binds tighter, so "-9223372036854775808 ** 2" still negates a power
+            // and its operand is too large.
+            let is_negative_int = op == UnaryOp::Neg
+                && pear.next_is(TokenKind::NumericLiteral)
+                && pear
+                    .peek_n(2)
+                    .is_none_or(|token| token.kind != TokenKind::StarStar);
+            if is_negative_int {
+                let token = pear.tag(TokenKind::NumericLiteral)?;
+                let int = IntLiteral::parse_negative(token)?;
+                return Ok(Self::Literal(Literal::Int(int)));
+            }
+
+            // "**" binds tighter than a prefix operator, so "-2 ** 2" is -(2 ** 2)
+            let operand = Self::parse_binary(pear, BinOp::Pow.precedence())?;
+            return Ok(Self::Unary(op, Box::new(operand)));
+        }
+
+        if next.kind == TokenKind::OpenParen {
+            pear.tag(TokenKind::OpenParen)?;
+            let expr = Self::parse_binary(pear, 0)?;
+            pear.tag(TokenKind::CloseParen).map_err(|e| ParseError {
+                kind: ParseErrorKind::Unclosed,
+                span: next.span,
+                ..e
+            })?;
+            return Ok(expr);
+        }
 
-        let is_literal =
-            (next.kind == TokenKind::StringLiteral) | (next.kind == TokenKind::NumericLiteral);
+        let is_literal = matches!(
+            next.kind,
+            TokenKind::StringLiteral
+                | TokenKind::RawStringLiteral
+                | TokenKind::UnterminatedString
+                | TokenKind::CharLiteral
+                | TokenKind::UnterminatedChar
+                | TokenKind::ByteStringLiteral
+                | TokenKind::NumericLiteral
+                | TokenKind::FloatLiteral
+                | TokenKind::True
+                | TokenKind::False
+        );
         if is_literal {
             let literal = Literal::try_from(&mut *pear)?;
             return Ok(Self::Literal(literal));
         }
 
-        let is_function = next.kind == TokenKind::Func;
-        if is_function {
+        let is_interpolation = next.kind == TokenKind::StringStart;
+        if is_interpolation {
+            let interpolation = Interpolation::try_from(&mut *pear)?;
+            return Ok(Self::Interpolation(interpolation));
+        }
+
+        let is_call = next.kind == TokenKind::Ident
+            && pear
+                .peek_n(2)
+                .is_some_and(|token| token.kind == TokenKind::OpenParen);
+        if is_call {
             let func = FunctionInvocation::try_from(&mut *pear)?;
             return Ok(Self::FunctionInvocation(func));
         }
@@ -55,3 +165,408 @@ impl TryFrom<&mut Pear<'_>> for Expr {
         Ok(Self::Literal(literal))
     }
 }
+
+impl TryFrom<&mut Pear<'_>> for Expr {
+    type Error = ParseError;
+
+    fn try_from(pear: &mut Pear) -> Result<Self, Self::Error> {
+        Self::parse_binary(pear, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::binary_operation::BinOp;
+    use crate::ast::expression::Expr;
+    use crate::ast::identifier::Ident;
+    use crate::ast::literal::{IntLiteral, Literal, Radix};
+    use crate::ast::statement::binding::BindingUsage;
+    use crate::ast::unary_operation::UnaryOp;
+    use crate::env::Env;
+    use crate::lexer::{Span, TokenKind};
+    use crate::pear::error::{Found, ParseError, ParseErrorKind};
+    use crate::pear::Pear;
+    use crate::val::Val;
+
+    fn parse(s: &str) -> Result<Expr, ParseError> {
+        Expr::try_from(&mut Pear::from(s))
+    }
+
+    fn eval(s: &str) -> Result<Val, String> {
+        parse(s).unwrap().eval(&Env::default())
+    }
+
+    fn int(value: i64) -> Box<Expr> {
+        Box::new(Expr::Literal(Literal::Int(IntLiteral::new(
+            value,
+            Radix::Decimal,
+        ))))
+    }
+
+    #[test]
+    fn parse_binary_with_precedence() {
+        assert_eq!(
+            parse("1 + 2 * 3"),
+            Ok(Expr::Binary(
+                BinOp::Add,
+                int(1),
+                Box::new(Expr::Binary(BinOp::Mul, int(2), int(3)))
+            ))
+        );
+        assert_eq!(
+            parse("1 * 2 + 3"),
+            Ok(Expr::Binary(
+                BinOp::Add,
+                Box::new(Expr::Binary(BinOp::Mul, int(1), int(2))),
+                int(3)
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_binary_left_associative() {
+        assert_eq!(
+            parse("10 - 5 - 2"),
+            Ok(Expr::Binary(
+                BinOp::Sub,
+                Box::new(Expr::Binary(BinOp::Sub, int(10), int(5))),
+                int(2)
+            ))
+        );
+        assert_eq!(eval("10 - 5 - 2"), Ok(Val::Int(3)));
+        assert_eq!(eval("8 / 4 / 2"), Ok(Val::Int(1)));
+        assert_eq!(eval("10 + 5 * 2 - 3"), Ok(Val::Int(17)));
+        assert_eq!(eval("100 % 7 % 3"), Ok(Val::Int(2)));
+        assert_eq!(eval("12 / 3 % 3 * 5"), Ok(Val::Int(5)));
+    }
+
+    #[test]
+    fn parse_power_right_associative() {
+        assert_eq!(
+            parse("2 ** 3 ** 2"),
+            Ok(Expr::Binary(
+                BinOp::Pow,
+                int(2),
+                Box::new(Expr::Binary(BinOp::Pow, int(3), int(2)))
+            ))
+        );
+        assert_eq!(eval("2 ** 3 ** 2"), Ok(Val::Int(512)));
+        assert_eq!(eval("2 * 3 ** 2"), Ok(Val::Int(18)));
+        assert_eq!(eval("2.0 ** -1"), Ok(Val::Float(0.5)));
+    }
+
+    #[test]
+    fn parse_bitwise_and_shifts_with_precedence() {
+        assert_eq!(
+            parse("1 | 2 ^ 3 & 4 << 5 + 6"),
+            Ok(Expr::Binary(
+                BinOp::BitOr,
+                int(1),
+                Box::new(Expr::Binary(
+                    BinOp::BitXor,
+                    int(2),
+                    Box::new(Expr::Binary(
+                        BinOp::BitAnd,
+                        int(3),
+                        Box::new(Expr::Binary(
+                            BinOp::Shl,
+                            int(4),
+                            Box::new(Expr::Binary(BinOp::Add, int(5), int(6)))
+                        ))
+                    ))
+                ))
+            ))
+        );
+        assert_eq!(eval("1 << 4 >> 2"), Ok(Val::Int(4)));
+        assert_eq!(eval("0xff & 0x0f | 0x30 ^ 0x10"), Ok(Val::Int(0x2f)));
+        assert_eq!(eval("17 % 5 << 1"), Ok(Val::Int(4)));
+        assert_eq!(
+            eval("1 << 2 - 3"),
+            Err("Negative shift amount in 1 Shl -1".to_string())
+        );
+        assert_eq!(eval("10 % (5 - 5)"), Err("Modulo by zero".to_string()));
+    }
+
+    #[test]
+    fn parse_power_binds_tighter_than_unary() {
+        assert_eq!(
+            parse("-2 ** 2"),
+            Ok(Expr::Unary(
+                UnaryOp::Neg,
+                Box::new(Expr::Binary(BinOp::Pow, int(2), int(2)))
+            ))
+        );
+        assert_eq!(eval("-2 ** 2"), Ok(Val::Int(-4)));
+        assert_eq!(eval("(-2) ** 2"), Ok(Val::Int(4)));
+        assert_eq!(
+            eval("2 ** -2 ** 2"),
+            Err("Integer exponent must be between 0 and 4294967295, got -4".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_parenthesised() {
+        assert_eq!(
+            parse("(1 + 2) * 3"),
+            Ok(Expr::Binary(
+                BinOp::Mul,
+                Box::new(Expr::Binary(BinOp::Add, int(1), int(2))),
+                int(3)
+            ))
+        );
+        assert_eq!(
+            parse("((a))"),
+            Ok(Expr::BindingUsage(BindingUsage {
+                name: Ident("a".to_string())
+            }))
+        );
+        assert_eq!(eval("((2 * (3 + 4)) - (1))"), Ok(Val::Int(13)));
+    }
+
+    #[test]
+    fn parse_binary_stops_at_other_tokens() {
+        let mut pear = Pear::from("1 + 2; 3");
+        assert_eq!(
+            Expr::try_from(&mut pear),
+            Ok(Expr::Binary(BinOp::Add, int(1), int(2)))
+        );
+        assert_eq!(pear.peek_next().map(|t| t.kind), Some(TokenKind::Semi));
+    }
+
+    #[test]
+    fn parse_bad_binary() {
+        let missing = parse("1 +").unwrap_err();
+        assert_eq!(missing.kind, ParseErrorKind::MissingExpression);
+        assert_eq!(missing.span, Span::new(2, 3, 1, 3));
+        assert_eq!(missing.found, None);
+
+        let missing = parse("1 + ;").unwrap_err();
+        assert_eq!(missing.kind, ParseErrorKind::MissingExpression);
+        assert_eq!(
+            missing.found,
+            Some(Found {
+                kind: TokenKind::Semi,
+                text: ";".to_string()
+            })
+        );
+
+        let unclosed = parse("\n (1 + 2").unwrap_err();
+        assert_eq!(unclosed.kind, ParseErrorKind::Unclosed);
+        assert_eq!(unclosed.span, Span::new(2, 3, 2, 2));
+        assert!(unclosed.expected.contains(&TokenKind::CloseParen));
+        assert_eq!(unclosed.found, None);
+
+        let unclosed = parse("(1 2)").unwrap_err();
+        assert_eq!(unclosed.kind, ParseErrorKind::Unclosed);
+        assert_eq!(unclosed.span, Span::new(0, 1, 1, 1));
+        assert_eq!(
+            unclosed.found.map(|found| found.text),
+            Some("2".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_unary() {
+        // Folded into the literal, see parse_negative_int_literals
+        assert_eq!(parse("-5"), Ok(*int(-5)));
+        assert_eq!(parse("-(5)"), Ok(Expr::Unary(UnaryOp::Neg, int(5))));
+        assert_eq!(
+            parse("-x ** 2"),
+            Ok(Expr::Unary(
+                UnaryOp::Neg,
+                Box::new(Expr::Binary(
+                    BinOp::Pow,
+                    Box::new(Expr::BindingUsage(BindingUsage {
+                        name: Ident("x".to_string())
+                    })),
+                    int(2)
+                ))
+            ))
+        );
+        assert_eq!(
+            parse("!-+a"),
+            Ok(Expr::Unary(
+                UnaryOp::Not,
+                Box::new(Expr::Unary(
+                    UnaryOp::Neg,
+                    Box::new(Expr::Unary(
+                        UnaryOp::Plus,
+                        Box::new(Expr::BindingUsage(BindingUsage {
+                            name: Ident("a".to_string())
+                        }))
+                    ))
+                ))
+            ))
+        );
+        assert_eq!(
+            parse("-(1 + 2)"),
+            Ok(Expr::Unary(
+                UnaryOp::Neg,
+                Box::new(Expr::Binary(BinOp::Add, int(1), int(2)))
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_unary_binds_tighter_than_binary() {
+        let negated = |value| {
+            Box::new(Expr::Unary(
+                UnaryOp::Neg,
+                Box::new(Expr::Unary(UnaryOp::Plus, int(value))),
+            ))
+        };
+
+        assert_eq!(
+            parse("-+2 * 3"),
+            Ok(Expr::Binary(BinOp::Mul, negated(2), int(3)))
+        );
+        assert_eq!(
+            parse("1 - -+2"),
+            Ok(Expr::Binary(BinOp::Sub, int(1), negated(2)))
+        );
+        assert_eq!(
+            parse("1 + !2 * 3"),
+            Ok(Expr::Binary(
+                BinOp::Add,
+                int(1),
+                Box::new(Expr::Binary(
+                    BinOp::Mul,
+                    Box::new(Expr::Unary(UnaryOp::Not, int(2))),
+                    int(3)
+                ))
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_negative_int_literals() {
+        assert_eq!(parse("-9223372036854775808"), Ok(*int(i64::MIN)));
+        assert_eq!(
+            parse("-0x10"),
+            Ok(Expr::Literal(Literal::Int(IntLiteral::new(
+                -16,
+                Radix::Hexadecimal
+            ))))
+        );
+        assert_eq!(
+            parse("1 - -2"),
+            Ok(Expr::Binary(BinOp::Sub, int(1), int(-2)))
+        );
+
+        let too_large = parse("-9223372036854775809").unwrap_err();
+        assert_eq!(too_large.kind, ParseErrorKind::InvalidLiteral);
+        assert_eq!(too_large.span, Span::new(1, 20, 1, 2));
+        let positive = parse("9223372036854775808").unwrap_err();
+        assert_eq!(positive.kind, ParseErrorKind::InvalidLiteral);
+
+        // Only a literal right after the "-" is folded, and "**" takes the literal first
+        let power = parse("-9223372036854775808 ** 2").unwrap_err();
+        assert_eq!(power.kind, ParseErrorKind::InvalidLiteral);
+        assert_eq!(
+            power.notes,
+            vec!["Too large for a 64-bit integer".to_string()]
+        );
+        assert_eq!(
+            parse("(-9223372036854775808) ** 2"),
+            Ok(Expr::Binary(BinOp::Pow, int(i64::MIN), int(2)))
+        );
+
+        assert_eq!(eval("-9223372036854775808"), Ok(Val::Int(i64::MIN)));
+        assert_eq!(
+            eval("--9223372036854775808"),
+            Err("Integer overflow in --9223372036854775808".to_string())
+        );
+    }
+
+    #[test]
+    fn eval_unary() {
+        assert_eq!(eval("-5"), Ok(Val::Int(-5)));
+        assert_eq!(eval("--5"), Ok(Val::Int(5)));
+        assert_eq!(eval("+5 - -5"), Ok(Val::Int(10)));
+        assert_eq!(eval("-2.5 * 2.0"), Ok(Val::Float(-5.0)));
+        assert_eq!(eval("-(1 + 2) * 3"), Ok(Val::Int(-9)));
+        assert_eq!(eval("!0"), Ok(Val::Int(-1)));
+        assert_eq!(
+            eval(r#"-"a""#),
+            Err(r#"Unsupported operation, operand: Str("a") | op: Neg"#.to_string())
+        );
+        let missing = parse("1 * -").unwrap_err();
+        assert_eq!(missing.kind, ParseErrorKind::MissingExpression);
+        assert_eq!(missing.span, Span::new(4, 5, 1, 5));
+    }
+
+    #[test]
+    fn parse_not_binds_tighter_than_and() {
+        let usage = |name: &str| {
+            Box::new(Expr::BindingUsage(BindingUsage {
+                name: Ident(name.to_string()),
+            }))
+        };
+
+        assert_eq!(
+            parse("!a && b"),
+            Ok(Expr::Binary(
+                BinOp::And,
+                Box::new(Expr::Unary(UnaryOp::Not, usage("a"))),
+                usage("b")
+            ))
+        );
+        assert_eq!(
+            parse("a || b && c == d"),
+            Ok(Expr::Binary(
+                BinOp::Or,
+                usage("a"),
+                Box::new(Expr::Binary(
+                    BinOp::And,
+                    usage("b"),
+                    Box::new(Expr::Binary(BinOp::Eq, usage("c"), usage("d")))
+                ))
+            ))
+        );
+    }
+
+    #[test]
+    fn eval_comparisons_and_logical() {
+        assert_eq!(eval("1 + 2 < 4"), Ok(Val::Bool(true)));
+        assert_eq!(eval("2 * 3 >= 7 == false"), Ok(Val::Bool(true)));
+        assert_eq!(eval("1 & 3 != 0"), Ok(Val::Bool(true)));
+        assert_eq!(eval(r#""a" + "b" == "ab""#), Ok(Val::Bool(true)));
+        assert_eq!(eval("!true || 1 <= 1 && 'a' > 'b'"), Ok(Val::Bool(false)));
+        assert_eq!(eval("!false && true"), Ok(Val::Bool(true)));
+        assert_eq!(
+            eval("1 < 2.0"),
+            Err("Unsupported operation, lhs: Int(1) | rhs: Float(2.0) | op: Lt".to_string())
+        );
+    }
+
+    #[test]
+    fn eval_logical_short_circuits() {
+        // The right-hand sides would fail if they were evaluated
+        assert_eq!(eval("false && missing"), Ok(Val::Bool(false)));
+        assert_eq!(eval("true || 1 / 0 == 1"), Ok(Val::Bool(true)));
+        assert_eq!(eval("1 > 2 && { missing }"), Ok(Val::Bool(false)));
+
+        assert_eq!(
+            eval("true && missing"),
+            Err("Binding does not exist: missing".to_string())
+        );
+        assert_eq!(
+            eval("1 && true"),
+            Err("Unsupported operation, lhs: Int(1) | rhs: Bool(true) | op: And".to_string())
+        );
+    }
+
+    #[test]
+    fn eval_binary() {
+        assert_eq!(eval("1.5 * 2.0 + 0.5"), Ok(Val::Float(3.5)));
+        assert_eq!(
+            eval(r#""Hello, " + "world""#),
+            Ok(Val::Str("Hello, world".to_string()))
+        );
+        assert_eq!(eval("1 / 0"), Err("Division by zero".to_string()));
+        assert_eq!(
+            eval(r#"1 - "a""#),
+            Err(r#"Unsupported operation, lhs: Int(1) | rhs: Str("a") | op: Sub"#.to_string())
+        );
+    }
+}
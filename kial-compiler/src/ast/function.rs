@@ -1,44 +1,424 @@
 use crate::ast::block::Block;
+use crate::ast::expression::Expr;
 use crate::ast::identifier::Ident;
+use crate::env::Env;
+use crate::lexer::{Token, TokenKind};
+use crate::pear::error::{ParseError, ParseErrorKind};
 use crate::pear::Pear;
+use crate::trace;
+use crate::val::Val;
 
+/// The name of a type, e.g. "i32" or "str"
 #[derive(Debug, PartialEq)]
-pub(crate) struct FunctionDefinition {
-    name: Ident,
-    body: Block,
+pub(crate) struct Type(pub(crate) Ident);
+
+impl Type {
+    /// Parses the type that has to follow `after`, which is either ":" or "->"
+    fn parse_after(pear: &mut Pear, after: Token) -> Result<Self, ParseError> {
+        if !pear.next_is(TokenKind::Ident) {
+            return Err(pear
+                .error(ParseErrorKind::MissingType)
+                .note(format!("\"{after}\" has to be followed by a type")));
+        }
+
+        Ok(Self(Ident::try_from(&mut *pear)?))
+    }
 }
 
+/// A parameter of a function definition, e.g. "a: i32" or just "a"
 #[derive(Debug, PartialEq)]
-enum Arg {
-    Identifier,
-    Literal,
+pub(crate) struct Param {
+    pub(crate) name: Ident,
+    pub(crate) ty: Option<Type>,
+}
+
+impl TryFrom<&mut Pear<'_>> for Param {
+    type Error = ParseError;
+
+    fn try_from(pear: &mut Pear<'_>) -> Result<Self, Self::Error> {
+        let _trace = trace::enter("Param", pear);
+        let name = Ident::try_from(&mut *pear)?;
+
+        let ty = match pear.peek_next() {
+            Some(colon) if colon.kind == TokenKind::Colon => {
+                pear.tag(TokenKind::Colon)?;
+                Some(Type::parse_after(pear, colon)?)
+            }
+            _ => None,
+        };
+
+        Ok(Self { name, ty })
+    }
+}
+
+/// "func name(a: i32, b) -> i32 { ... }"
+#[derive(Debug, PartialEq)]
+pub(crate) struct FunctionDefinition {
+    pub(crate) name: Ident,
+    pub(crate) params: Vec<Param>,
+    pub(crate) return_type: Option<Type>,
+    pub(crate) body: Block,
+}
+
+impl FunctionDefinition {
+    fn parse_params(pear: &mut Pear, name: Token) -> Result<Vec<Param>, ParseError> {
+        let open = pear.tag(TokenKind::OpenParen)?;
+        let unclosed = || {
+            ParseError::new(ParseErrorKind::Unclosed, open.span).expecting([TokenKind::CloseParen])
+        };
+
+        let mut params: Vec<Param> = vec![];
+        loop {
+            let next = pear.peek_next().ok_or_else(unclosed)?;
+            match next.kind {
+                TokenKind::CloseParen => break,
+                kind if kind == TokenKind::Ident || kind.is_keyword() => {
+                    let param = Param::try_from(&mut *pear)?;
+                    if params.iter().any(|p| p.name == param.name) {
+                        return Err(ParseError::new(ParseErrorKind::Duplicate, next.span)
+                            .found(Some(next))
+                            .note(format!("in the parameters of \"{name}\"")));
+                    }
+                    params.push(param);
+                }
+                _ => return Err(pear.error(ParseErrorKind::MissingName)),
+            }
+
+            let next = pear.peek_next().ok_or_else(unclosed)?;
+            match next.kind {
+                TokenKind::Comma => {
+                    pear.tag(TokenKind::Comma)?;
+                }
+                TokenKind::CloseParen => break,
+                _ => return Err(pear.unexpected([TokenKind::Comma, TokenKind::CloseParen])),
+            }
+        }
+
+        pear.tag(TokenKind::CloseParen)?;
+        Ok(params)
+    }
 }
 
+impl TryFrom<&mut Pear<'_>> for FunctionDefinition {
+    type Error = ParseError;
+
+    fn try_from(pear: &mut Pear<'_>) -> Result<Self, Self::Error> {
+        let _trace = trace::enter("FunctionDefinition", pear);
+        let func = pear.tag(TokenKind::Func)?;
+
+        let Some(name_token) = pear
+            .peek_next()
+            .filter(|token| token.kind == TokenKind::Ident || token.kind.is_keyword())
+        else {
+            return Err(pear.error(ParseErrorKind::MissingName).note(format!(
+                "\"{func}\" has to be followed by the function's name"
+            )));
+        };
+        let name = Ident::try_from(&mut *pear)?;
+        let params = Self::parse_params(pear, name_token)?;
+
+        let return_type = match pear.peek_next() {
+            Some(arrow) if arrow.kind == TokenKind::Arrow => {
+                pear.tag(TokenKind::Arrow)?;
+                Some(Type::parse_after(pear, arrow)?)
+            }
+            _ => None,
+        };
+
+        if !pear.next_is(TokenKind::OpenBrace) {
+            return Err(pear.unexpected([TokenKind::OpenBrace]).note(format!(
+                "The body of \"{name_token}\" goes between \"{{\" and \"}}\""
+            )));
+        }
+        let body = Block::try_from(&mut *pear)?;
+
+        Ok(Self {
+            name,
+            params,
+            return_type,
+            body,
+        })
+    }
+}
+
+/// The parenthesised, comma separated arguments of a call, e.g. "(a, 1 + 2,)"
 #[derive(Debug, PartialEq)]
-struct ArgumentList {
-    args: Vec<Arg>,
+pub(crate) struct ArgumentList {
+    pub(crate) args: Vec<Expr>,
 }
 
 impl TryFrom<&mut Pear<'_>> for ArgumentList {
-    type Error = String;
+    type Error = ParseError;
+
     fn try_from(pear: &mut Pear<'_>) -> Result<Self, Self::Error> {
-        unreachable!()
+        let _trace = trace::enter("ArgumentList", pear);
+        let open = pear.tag(TokenKind::OpenParen)?;
+        let unclosed = || {
+            ParseError::new(ParseErrorKind::Unclosed, open.span).expecting([TokenKind::CloseParen])
+        };
+
+        let mut args = vec![];
+        loop {
+            let next = pear.peek_next().ok_or_else(unclosed)?;
+            match next.kind {
+                TokenKind::CloseParen => break,
+                TokenKind::Comma => return Err(pear.error(ParseErrorKind::MissingExpression)),
+                _ => args.push(Expr::try_from(&mut *pear)?),
+            }
+
+            let next = pear.peek_next().ok_or_else(unclosed)?;
+            match next.kind {
+                TokenKind::Comma => {
+                    pear.tag(TokenKind::Comma)?;
+                }
+                TokenKind::CloseParen => break,
+                _ => return Err(pear.unexpected([TokenKind::Comma, TokenKind::CloseParen])),
+            }
+        }
+
+        pear.tag(TokenKind::CloseParen)?;
+        Ok(Self { args })
     }
 }
 
 #[derive(Debug, PartialEq)]
 pub(crate) struct FunctionInvocation {
-    name: Ident,
-    arguments: ArgumentList,
+    pub(crate) name: Ident,
+    pub(crate) arguments: ArgumentList,
+}
+
+impl FunctionInvocation {
+    pub(crate) fn eval(&self, _env: &Env) -> Result<Val, String> {
+        Err(format!(
+            "Can't call \"{}\", function calls aren't supported yet",
+            self.name.0
+        ))
+    }
 }
 
 impl TryFrom<&mut Pear<'_>> for FunctionInvocation {
-    type Error = String;
+    type Error = ParseError;
 
     fn try_from(pear: &mut Pear<'_>) -> Result<Self, Self::Error> {
+        let _trace = trace::enter("FunctionInvocation", pear);
         let name = Ident::try_from(&mut *pear)?;
         let arguments = ArgumentList::try_from(&mut *pear)?;
 
         Ok(Self { name, arguments })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::binary_operation::BinOp;
+    use crate::ast::block::Block;
+    use crate::ast::expression::Expr;
+    use crate::ast::function::{ArgumentList, FunctionDefinition, FunctionInvocation, Param, Type};
+    use crate::ast::identifier::Ident;
+    use crate::ast::literal::{IntLiteral, Literal, Radix};
+    use crate::lexer::TokenKind;
+    use crate::pear::error::{ParseError, ParseErrorKind};
+    use crate::pear::Pear;
+    use std::collections::BTreeSet;
+    use std::fmt::Debug;
+
+    fn int(value: i64) -> Expr {
+        Expr::Literal(Literal::Int(IntLiteral::new(value, Radix::Decimal)))
+    }
+
+    fn call(name: &str, args: Vec<Expr>) -> Expr {
+        Expr::FunctionInvocation(FunctionInvocation {
+            name: Ident(name.to_string()),
+            arguments: ArgumentList { args },
+        })
+    }
+
+    fn parse(s: &str) -> Result<Expr, ParseError> {
+        Expr::try_from(&mut Pear::from(s))
+    }
+
+    /// The kind of error `result` failed with, its line and column, and the text it found
+    fn error<T: Debug>(
+        result: Result<T, ParseError>,
+    ) -> (ParseErrorKind, (usize, usize), Option<String>) {
+        let error = result.unwrap_err();
+        let position = (error.span.line, error.span.column);
+        (error.kind, position, error.found.map(|found| found.text))
+    }
+
+    fn found(text: &str) -> Option<String> {
+        Some(text.to_string())
+    }
+
+    #[test]
+    fn parse_calls() {
+        assert_eq!(parse("f()"), Ok(call("f", vec![])));
+        assert_eq!(parse("f(1)"), Ok(call("f", vec![int(1)])));
+        assert_eq!(parse("f(1, 2,)"), Ok(call("f", vec![int(1), int(2)])));
+        assert_eq!(
+            parse("f(g(1), 2 + 3)"),
+            Ok(call(
+                "f",
+                vec![
+                    call("g", vec![int(1)]),
+                    Expr::Binary(BinOp::Add, Box::new(int(2)), Box::new(int(3)))
+                ]
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_calls_in_expressions() {
+        assert_eq!(
+            parse("1 + f(2) * g()"),
+            Ok(Expr::Binary(
+                BinOp::Add,
+                Box::new(int(1)),
+                Box::new(Expr::Binary(
+                    BinOp::Mul,
+                    Box::new(call("f", vec![int(2)])),
+                    Box::new(call("g", vec![]))
+                ))
+            ))
+        );
+        assert_eq!(
+            error(parse("f((1), (g)(2))")),
+            (ParseErrorKind::UnexpectedToken, (1, 11), found("("))
+        );
+    }
+
+    #[test]
+    fn parse_bad_argument_lists() {
+        let missing_separator = parse("f(1 2)").unwrap_err();
+        assert_eq!(
+            missing_separator.expected,
+            BTreeSet::from([TokenKind::Comma, TokenKind::CloseParen])
+        );
+        assert_eq!(
+            error(Err::<(), _>(missing_separator)),
+            (ParseErrorKind::UnexpectedToken, (1, 5), found("2"))
+        );
+        assert_eq!(
+            error(parse("f(,)")),
+            (ParseErrorKind::MissingExpression, (1, 3), found(","))
+        );
+        assert_eq!(
+            error(parse("f(1,,)")),
+            (ParseErrorKind::MissingExpression, (1, 5), found(","))
+        );
+        assert_eq!(
+            error(parse("f(1,\n  g(2")),
+            (ParseErrorKind::Unclosed, (2, 4), None)
+        );
+    }
+
+    fn ident(name: &str) -> Ident {
+        Ident(name.to_string())
+    }
+
+    fn parse_definition(s: &str) -> Result<FunctionDefinition, ParseError> {
+        FunctionDefinition::try_from(&mut Pear::from(s))
+    }
+
+    #[test]
+    fn parse_definitions() {
+        assert_eq!(
+            parse_definition("func main() {}"),
+            Ok(FunctionDefinition {
+                name: ident("main"),
+                params: vec![],
+                return_type: None,
+                body: Block::try_from(&mut Pear::from("{}")).unwrap(),
+            })
+        );
+
+        let add = parse_definition("func add(a: i32, b: str, c,) -> i32 { a }").unwrap();
+        assert_eq!(
+            add.params,
+            vec![
+                Param {
+                    name: ident("a"),
+                    ty: Some(Type(ident("i32")))
+                },
+                Param {
+                    name: ident("b"),
+                    ty: Some(Type(ident("str")))
+                },
+                Param {
+                    name: ident("c"),
+                    ty: None
+                },
+            ]
+        );
+        assert_eq!(add.return_type, Some(Type(ident("i32"))));
+        assert_eq!(add.body, Block::try_from(&mut Pear::from("{ a }")).unwrap());
+    }
+
+    #[test]
+    fn parse_bad_definitions() {
+        let err = |s| error(parse_definition(s));
+
+        assert_eq!(
+            err("func (a) {}"),
+            (ParseErrorKind::MissingName, (1, 6), found("("))
+        );
+        assert_eq!(
+            err("func while() {}"),
+            (ParseErrorKind::ReservedKeyword, (1, 6), found("while"))
+        );
+        assert_eq!(
+            err("func f(while) {}"),
+            (ParseErrorKind::ReservedKeyword, (1, 8), found("while"))
+        );
+        assert_eq!(
+            err("func f {}"),
+            (ParseErrorKind::UnexpectedToken, (1, 8), found("{"))
+        );
+        assert_eq!(
+            err("func f(a b) {}"),
+            (ParseErrorKind::UnexpectedToken, (1, 10), found("b"))
+        );
+        assert_eq!(
+            err("func f(a:) {}"),
+            (ParseErrorKind::MissingType, (1, 10), found(")"))
+        );
+        assert_eq!(
+            err("func f(, a) {}"),
+            (ParseErrorKind::MissingName, (1, 8), found(","))
+        );
+        assert_eq!(
+            err("func f(1) {}"),
+            (ParseErrorKind::MissingName, (1, 8), found("1"))
+        );
+        assert_eq!(
+            err("func f(a, b: i32, a) {}"),
+            (ParseErrorKind::Duplicate, (1, 19), found("a"))
+        );
+        assert_eq!(
+            err("func f() -> {}"),
+            (ParseErrorKind::MissingType, (1, 13), found("{"))
+        );
+        assert_eq!(
+            err("func f() -> i32"),
+            (ParseErrorKind::UnexpectedToken, (1, 13), None)
+        );
+        assert_eq!(
+            err("func f(a: i32"),
+            (ParseErrorKind::Unclosed, (1, 7), None)
+        );
+        assert_eq!(
+            err("func f(a:"),
+            (ParseErrorKind::MissingType, (1, 9), None)
+        );
+
+        let missing_body = parse_definition("func f() -> i32").unwrap_err();
+        assert_eq!(
+            missing_body.expected,
+            BTreeSet::from([TokenKind::OpenBrace])
+        );
+        assert_eq!(
+            missing_body.to_string(),
+            "1:13: Expected \"{\", found end of input\n  note: The body of \"f\" goes between \"{\" and \"}\""
+        );
+    }
+}
@@ -1,13 +1,43 @@
+use crate::pear::error::ParseError;
 use crate::pear::Pear;
+use crate::trace;
+use unicode_normalization::UnicodeNormalization;
 
 #[derive(Debug, PartialEq)]
 pub(crate) struct Ident(pub String);
 
 impl TryFrom<&mut Pear<'_>> for Ident {
-    type Error = String;
+    type Error = ParseError;
 
     fn try_from(pear: &mut Pear) -> Result<Self, Self::Error> {
-        let identifier = pear.extract_identifier()?.val;
+        let _trace = trace::enter("Ident", pear);
+        // NFC normalization makes "é" and "e\u{301}" the same name
+        let identifier = pear.extract_identifier()?.val.nfc().collect();
         Ok(Self(identifier))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::identifier::Ident;
+    use crate::pear::Pear;
+
+    #[test]
+    fn parse_snake_case_ident() {
+        let mut pear = Pear::from("my_long_var_name");
+        assert_eq!(
+            Ident::try_from(&mut pear),
+            Ok(Ident("my_long_var_name".to_string()))
+        );
+        assert_eq!(pear.peek_next(), None);
+    }
+
+    #[test]
+    fn parse_ident_nfc_normalized() {
+        let decomposed = Ident::try_from(&mut Pear::from("cafe\u{301}"));
+        let composed = Ident::try_from(&mut Pear::from("caf\u{e9}"));
+
+        assert_eq!(decomposed, Ok(Ident("caf\u{e9}".to_string())));
+        assert_eq!(decomposed, composed);
+    }
+}
@@ -0,0 +1,187 @@
+use crate::ast::expression::Expr;
+use crate::env::Env;
+use crate::lexer::{self, Token, TokenKind};
+use crate::pear::error::{ParseError, ParseErrorKind};
+use crate::pear::Pear;
+use crate::trace;
+use crate::val::Val;
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum InterpolationPart {
+    Str(String),
+    Expr(Expr),
+}
+
+/// A string like "Hello {name}", made of literal text and embedded expressions. Braces in the
+/// text are escaped, as in "\{not interpolated\}".
+#[derive(Debug, PartialEq)]
+pub(crate) struct Interpolation {
+    pub(crate) parts: Vec<InterpolationPart>,
+}
+
+impl Interpolation {
+    pub(crate) fn eval(&self, env: &Env) -> Result<Val, String> {
+        let mut str = String::new();
+        for part in &self.parts {
+            match part {
+                InterpolationPart::Str(s) => str.push_str(s),
+                InterpolationPart::Expr(expr) => str.push_str(&expr.eval(env)?.to_string()),
+            }
+        }
+
+        Ok(Val::Str(str))
+    }
+
+    /// The text of a "...{", "}...{" or "}..." token, without the quote and braces around it. The
+    /// lexer ends an interpolation that is never closed with a lone '"', which has no text.
+    fn contents(token: Token) -> Result<String, ParseError> {
+        let contents = token.val.get(1..token.val.len() - 1).unwrap_or_default();
+        lexer::unescape(contents).map_err(|e| ParseError::invalid_literal(token, e))
+    }
+}
+
+impl TryFrom<&mut Pear<'_>> for Interpolation {
+    type Error = ParseError;
+
+    fn try_from(pear: &mut Pear) -> Result<Self, Self::Error> {
+        let _trace = trace::enter("Interpolation", pear);
+        let start = pear.tag(TokenKind::StringStart)?;
+        let unterminated = || {
+            ParseError::invalid_literal(start, "Unterminated string literal, missing closing quote")
+        };
+
+        let mut parts = vec![InterpolationPart::Str(Self::contents(start)?)];
+        loop {
+            let next = pear.peek_next().ok_or_else(unterminated)?;
+
+            if matches!(next.kind, TokenKind::StringMiddle | TokenKind::StringEnd) {
+                return Err(pear
+                    .error(ParseErrorKind::MissingExpression)
+                    .note("Interpolations need an expression between \"{\" and \"}\""));
+            }
+
+            parts.push(InterpolationPart::Expr(Expr::try_from(&mut *pear)?));
+
+            let next = pear.peek_next().ok_or_else(unterminated)?;
+
+            match next.kind {
+                TokenKind::StringMiddle => {
+                    let middle = pear.tag(TokenKind::StringMiddle)?;
+                    parts.push(InterpolationPart::Str(Self::contents(middle)?));
+                }
+                TokenKind::StringEnd => {
+                    let end = pear.tag(TokenKind::StringEnd)?;
+                    parts.push(InterpolationPart::Str(Self::contents(end)?));
+                    break;
+                }
+                TokenKind::UnterminatedString => return Err(unterminated()),
+                _ => return Err(pear.unexpected([TokenKind::CloseBrace])),
+            }
+        }
+
+        parts.retain(|part| !matches!(part, InterpolationPart::Str(s) if s.is_empty()));
+        Ok(Self { parts })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::expression::Expr;
+    use crate::ast::identifier::Ident;
+    use crate::ast::interpolation::{Interpolation, InterpolationPart};
+    use crate::ast::literal::{IntLiteral, Literal, Radix};
+    use crate::ast::statement::binding::BindingUsage;
+    use crate::env::Env;
+    use crate::lexer::{Span, TokenKind};
+    use crate::pear::error::{Found, ParseErrorKind};
+    use crate::pear::Pear;
+    use crate::val::Val;
+
+    #[test]
+    fn parse_interpolation() {
+        let mut pear = Pear::from(r#""Hello {name}, you are {30}\{!\}""#);
+        let interpolation = Interpolation::try_from(&mut pear).unwrap();
+
+        assert_eq!(
+            interpolation,
+            Interpolation {
+                parts: vec![
+                    InterpolationPart::Str("Hello ".to_string()),
+                    InterpolationPart::Expr(Expr::BindingUsage(BindingUsage {
+                        name: Ident("name".to_string())
+                    })),
+                    InterpolationPart::Str(", you are ".to_string()),
+                    InterpolationPart::Expr(Expr::Literal(Literal::Int(IntLiteral::new(
+                        30,
+                        Radix::Decimal
+                    )))),
+                    InterpolationPart::Str("{!}".to_string()),
+                ]
+            }
+        );
+        assert_eq!(pear.peek_next(), None);
+    }
+
+    #[test]
+    fn eval_interpolation() {
+        let mut env = Env::default();
+        env.store_binding("name".to_string(), Val::Str("Kial".to_string()));
+        env.store_binding("age".to_string(), Val::Int(3));
+
+        let mut pear = Pear::from(r#""Hello {name}, you are {age + 1}, or {{ let x = 2.5; x }}""#);
+        let expr = Expr::try_from(&mut pear).unwrap();
+
+        assert_eq!(
+            expr.eval(&env),
+            Ok(Val::Str("Hello Kial, you are 4, or 2.5".to_string()))
+        );
+    }
+
+    #[test]
+    fn eval_nested_interpolation() {
+        let mut env = Env::default();
+        env.store_binding("a".to_string(), Val::Int(1));
+
+        let mut pear = Pear::from(r#""<{"[{a}]"}>""#);
+        let expr = Expr::try_from(&mut pear).unwrap();
+        assert_eq!(expr.eval(&env), Ok(Val::Str("<[1]>".to_string())));
+
+        let mut pear = Pear::from(r#""{missing}""#);
+        let expr = Expr::try_from(&mut pear).unwrap();
+        assert_eq!(
+            expr.eval(&env),
+            Err("Binding does not exist: missing".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_bad_interpolations() {
+        let error = |s| Interpolation::try_from(&mut Pear::from(s)).unwrap_err();
+
+        let empty = error(r#""a {} b""#);
+        assert_eq!(empty.kind, ParseErrorKind::MissingExpression);
+        assert_eq!(empty.span, Span::new(4, 8, 1, 5));
+
+        let unclosed = error(r#""a {b c}""#);
+        assert_eq!(unclosed.kind, ParseErrorKind::UnexpectedToken);
+        assert_eq!(unclosed.span, Span::new(6, 7, 1, 7));
+        assert!(unclosed.expected.contains(&TokenKind::CloseBrace));
+        assert_eq!(
+            unclosed.found,
+            Some(Found {
+                kind: TokenKind::Ident,
+                text: "c".to_string()
+            })
+        );
+
+        for s in [r#""a {b} c"#, r#""a {b"#] {
+            let unterminated = error(s);
+            assert_eq!(unterminated.kind, ParseErrorKind::InvalidLiteral);
+            assert_eq!(unterminated.span, Span::new(0, 4, 1, 1));
+            assert_eq!(
+                unterminated.found.map(|found| found.kind),
+                Some(TokenKind::StringStart)
+            );
+        }
+    }
+}
@@ -1,32 +1,476 @@
-use crate::lexer::TokenKind;
+use crate::lexer::{self, Token, TokenKind};
+use crate::pear::error::{ParseError, ParseErrorKind};
 use crate::pear::Pear;
+use crate::trace;
+use crate::val::Val;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub(crate) enum Radix {
+    Binary = 2,
+    Octal = 8,
+    Decimal = 10,
+    Hexadecimal = 16,
+}
+
+impl Radix {
+    fn prefix(&self) -> &'static str {
+        match self {
+            Radix::Binary => "0b",
+            Radix::Octal => "0o",
+            Radix::Decimal => "",
+            Radix::Hexadecimal => "0x",
+        }
+    }
+}
+
+/// An integer literal, along with the radix it was written in
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub(crate) struct IntLiteral {
+    pub(crate) value: i64,
+    pub(crate) radix: Radix,
+}
+
+impl IntLiteral {
+    pub(crate) fn new(value: i64, radix: Radix) -> Self {
+        Self { value, radix }
+    }
+
+    fn parse(token: Token) -> Result<Self, ParseError> {
+        let (magnitude, radix) = Self::parse_magnitude(token)?;
+        let value = i64::try_from(magnitude).map_err(|_| Self::too_large(token))?;
+
+        Ok(Self::new(value, radix))
+    }
+
+    /// Parses `token` as the operand of a "-", which can go one further than a positive literal,
+    /// down to i64::MIN
+    pub(crate) fn parse_negative(token: Token) -> Result<Self, ParseError> {
+        let (magnitude, radix) = Self::parse_magnitude(token)?;
+        let value = 0i64
+            .checked_sub_unsigned(magnitude)
+            .ok_or_else(|| Self::too_large(token))?;
+
+        Ok(Self::new(value, radix))
+    }
+
+    fn parse_magnitude(token: Token) -> Result<(u64, Radix), ParseError> {
+        let text = token.val;
+        let radix = match text.get(..2) {
+            Some("0b") => Radix::Binary,
+            Some("0o") => Radix::Octal,
+            Some("0x") => Radix::Hexadecimal,
+            _ => Radix::Decimal,
+        };
+
+        let digits = text[radix.prefix().len()..].replace('_', "");
+        if digits.is_empty() {
+            return Err(ParseError::invalid_literal(
+                token,
+                format!("Expected digits after \"{text}\""),
+            ));
+        }
+
+        if let Some(bad) = digits.chars().find(|c| !c.is_digit(radix as u32)) {
+            return Err(ParseError::invalid_literal(
+                token,
+                format!("\"{bad}\" isn't a {radix:?} digit"),
+            ));
+        }
+
+        let magnitude =
+            u64::from_str_radix(&digits, radix as u32).map_err(|_| Self::too_large(token))?;
+
+        Ok((magnitude, radix))
+    }
+
+    fn too_large(token: Token) -> ParseError {
+        ParseError::invalid_literal(token, "Too large for a 64-bit integer")
+    }
+}
+
+impl Display for IntLiteral {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        // Written as a sign and a magnitude, rather than in two's complement
+        let sign = if self.value < 0 { "-" } else { "" };
+        let prefix = self.radix.prefix();
+        let magnitude = self.value.unsigned_abs();
+        match self.radix {
+            Radix::Binary => write!(f, "{sign}{prefix}{magnitude:b}"),
+            Radix::Octal => write!(f, "{sign}{prefix}{magnitude:o}"),
+            Radix::Decimal => write!(f, "{sign}{magnitude}"),
+            Radix::Hexadecimal => write!(f, "{sign}{prefix}{magnitude:x}"),
+        }
+    }
+}
 
 #[derive(Debug, PartialEq)]
 pub(crate) enum Literal {
     String(String),
-    Number(i32),
+    Char(char),
+    Bytes(Vec<u8>),
+    Int(IntLiteral),
+    Float(f64),
+    Bool(bool),
+}
+
+impl Literal {
+    pub(crate) fn eval(&self) -> Val {
+        match self {
+            Literal::String(str) => Val::Str(str.clone()),
+            Literal::Char(c) => Val::Char(*c),
+            Literal::Bytes(bytes) => Val::Bytes(bytes.clone()),
+            Literal::Int(int) => Val::Int(int.value),
+            Literal::Float(float) => Val::Float(*float),
+            Literal::Bool(bool) => Val::Bool(*bool),
+        }
+    }
+}
+
+impl Literal {
+    /// Unescapes the text of `token` between `open` and `close` bytes of delimiters
+    fn unescape(token: Token, open: usize, close: usize) -> Result<String, ParseError> {
+        let contents = &token.val[open..token.val.len() - close];
+        lexer::unescape(contents).map_err(|e| ParseError::invalid_literal(token, e))
+    }
+
+    fn parse_char(token: Token) -> Result<char, ParseError> {
+        let unescaped = Self::unescape(token, 1, 1)?;
+
+        let mut chars = unescaped.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            (None, _) => Err(ParseError::invalid_literal(token, "Empty char literal")),
+            (Some(_), Some(_)) => Err(ParseError::invalid_literal(
+                token,
+                "Char literals must contain exactly one character, use \"...\" for strings",
+            )),
+        }
+    }
+
+    fn parse_bytes(token: Token) -> Result<Vec<u8>, ParseError> {
+        // b"..." has "b\"" at the front and "\"" at the back
+        let unescaped = Self::unescape(token, 2, 1)?;
+
+        if let Some(c) = unescaped.chars().find(|c| !c.is_ascii()) {
+            return Err(ParseError::invalid_literal(
+                token,
+                format!("Byte strings can only contain ASCII, found \"{c}\""),
+            ));
+        }
+
+        Ok(unescaped.into_bytes())
+    }
 }
 
 impl TryFrom<&mut Pear<'_>> for Literal {
-    type Error = String;
+    type Error = ParseError;
 
     fn try_from(pear: &mut Pear) -> Result<Self, Self::Error> {
+        let _trace = trace::enter("Literal", pear);
         let Some(token) = pear.peek_next() else {
-            return Err("Expected literal, got \"None\"".to_string());
+            return Err(pear.error(ParseErrorKind::MissingExpression));
         };
 
         match token.kind {
             TokenKind::StringLiteral => {
                 let token = pear.tag(TokenKind::StringLiteral)?;
-                Ok(Literal::String(token.val))
+                Ok(Literal::String(Self::unescape(token, 1, 1)?))
+            }
+            TokenKind::RawStringLiteral => {
+                let token = pear.tag(TokenKind::RawStringLiteral)?;
+                // r##"..."## has "r##\"" at the front and "\"##" at the back
+                let hashes = token.val[1..].chars().take_while(|c| *c == '#').count();
+                let contents = &token.val[hashes + 2..token.val.len() - hashes - 1];
+                Ok(Literal::String(contents.to_string()))
+            }
+            TokenKind::UnterminatedString => Err(ParseError::invalid_literal(
+                token,
+                "Unterminated string literal, missing closing quote",
+            )),
+            TokenKind::CharLiteral => {
+                let token = pear.tag(TokenKind::CharLiteral)?;
+                Ok(Literal::Char(Self::parse_char(token)?))
+            }
+            TokenKind::UnterminatedChar => Err(ParseError::invalid_literal(
+                token,
+                "Unterminated char literal, missing closing quote",
+            )),
+            TokenKind::ByteStringLiteral => {
+                let token = pear.tag(TokenKind::ByteStringLiteral)?;
+                Ok(Literal::Bytes(Self::parse_bytes(token)?))
             }
             TokenKind::NumericLiteral => {
                 let token = pear.tag(TokenKind::NumericLiteral)?;
-                let val = token.val.parse::<i32>().unwrap(); // This may panic, may need a more graceful way to handle types larger than i32
-                Ok(Literal::Number(val))
+                Ok(Literal::Int(IntLiteral::parse(token)?))
+            }
+            TokenKind::FloatLiteral => {
+                let token = pear.tag(TokenKind::FloatLiteral)?;
+                let val = token
+                    .val
+                    .replace('_', "")
+                    .parse::<f64>()
+                    .map_err(|e| ParseError::invalid_literal(token, e.to_string()))?;
+                Ok(Literal::Float(val))
+            }
+            TokenKind::True | TokenKind::False => {
+                let token = pear.tag(token.kind)?;
+                Ok(Literal::Bool(token.kind == TokenKind::True))
             }
 
-            other => Err(format!("Expected literal, got \"{:?}\"", other)),
+            _ => Err(pear.error(ParseErrorKind::MissingExpression)),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::literal::{IntLiteral, Literal, Radix};
+    use crate::lexer::{Span, TokenKind};
+    use crate::pear::error::{Found, ParseError, ParseErrorKind};
+    use crate::pear::Pear;
+    use std::collections::BTreeSet;
+
+    fn parse(s: &str) -> Result<Literal, ParseError> {
+        Literal::try_from(&mut Pear::from(s))
+    }
+
+    /// The error for the literal `text` of `kind` at `span`, with `note` saying what's wrong
+    fn invalid(kind: TokenKind, text: &str, span: Span, note: &str) -> Result<Literal, ParseError> {
+        Err(ParseError {
+            kind: ParseErrorKind::InvalidLiteral,
+            span,
+            expected: BTreeSet::new(),
+            found: Some(Found {
+                kind,
+                text: text.to_string(),
+            }),
+            notes: vec![note.to_string()],
+        })
+    }
+
+    #[test]
+    fn parse_integers_keeping_radix() {
+        assert_eq!(
+            parse("42"),
+            Ok(Literal::Int(IntLiteral::new(42, Radix::Decimal)))
+        );
+        assert_eq!(
+            parse("1_000_000"),
+            Ok(Literal::Int(IntLiteral::new(1_000_000, Radix::Decimal)))
+        );
+        assert_eq!(
+            parse("0xff"),
+            Ok(Literal::Int(IntLiteral::new(255, Radix::Hexadecimal)))
+        );
+        assert_eq!(
+            parse("0o17"),
+            Ok(Literal::Int(IntLiteral::new(15, Radix::Octal)))
+        );
+        assert_eq!(
+            parse("0b1010"),
+            Ok(Literal::Int(IntLiteral::new(10, Radix::Binary)))
+        );
+        assert_eq!(
+            parse("0xFF_FF"),
+            Ok(Literal::Int(IntLiteral::new(0xffff, Radix::Hexadecimal)))
+        );
+    }
+
+    #[test]
+    fn parse_bad_integers() {
+        let int = TokenKind::NumericLiteral;
+        assert_eq!(
+            parse("0b102"),
+            invalid(
+                int,
+                "0b102",
+                Span::new(0, 5, 1, 1),
+                r#""2" isn't a Binary digit"#
+            )
+        );
+        assert_eq!(
+            parse("0x"),
+            invalid(
+                int,
+                "0x",
+                Span::new(0, 2, 1, 1),
+                r#"Expected digits after "0x""#
+            )
+        );
+        assert_eq!(
+            parse("9223372036854775808"),
+            invalid(
+                int,
+                "9223372036854775808",
+                Span::new(0, 19, 1, 1),
+                "Too large for a 64-bit integer"
+            )
+        );
+        assert_eq!(
+            parse(";").map_err(|e| (e.kind, e.span)),
+            Err((ParseErrorKind::MissingExpression, Span::new(0, 1, 1, 1)))
+        );
+    }
+
+    #[test]
+    fn display_integers_in_their_radix() {
+        assert_eq!(IntLiteral::new(255, Radix::Hexadecimal).to_string(), "0xff");
+        assert_eq!(IntLiteral::new(15, Radix::Octal).to_string(), "0o17");
+        assert_eq!(IntLiteral::new(10, Radix::Binary).to_string(), "0b1010");
+        assert_eq!(IntLiteral::new(1_000, Radix::Decimal).to_string(), "1000");
+        assert_eq!(
+            IntLiteral::new(-255, Radix::Hexadecimal).to_string(),
+            "-0xff"
+        );
+        assert_eq!(
+            IntLiteral::new(i64::MIN, Radix::Decimal).to_string(),
+            "-9223372036854775808"
+        );
+    }
+
+    #[test]
+    fn parse_floats() {
+        assert_eq!(parse("1.5"), Ok(Literal::Float(1.5)));
+        assert_eq!(parse("2e10"), Ok(Literal::Float(2e10)));
+        assert_eq!(parse("1_000.000_1"), Ok(Literal::Float(1000.0001)));
+        assert_eq!(parse("6.02E+23"), Ok(Literal::Float(6.02e23)));
+        assert_eq!(parse("1e-3"), Ok(Literal::Float(0.001)));
+    }
+
+    #[test]
+    fn parse_bools() {
+        assert_eq!(parse("true"), Ok(Literal::Bool(true)));
+        assert_eq!(parse("false"), Ok(Literal::Bool(false)));
+    }
+
+    #[test]
+    fn parse_string_with_escapes() {
+        let mut pear = Pear::from(r#""tab\there\n\"quoted\" \\ \u{1F600}""#);
+        assert_eq!(
+            Literal::try_from(&mut pear),
+            Ok(Literal::String("tab\there\n\"quoted\" \\ 😀".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_string_with_literal_braces() {
+        // A bare "{" would start an interpolation
+        assert_eq!(parse(r#""\{}\}""#), Ok(Literal::String("{}}".to_string())));
+    }
+
+    #[test]
+    fn parse_string_with_bad_escape() {
+        assert_eq!(
+            parse(r#"  "bad \q escape""#),
+            invalid(
+                TokenKind::StringLiteral,
+                r#""bad \q escape""#,
+                Span::new(2, 17, 1, 3),
+                r#"Unknown escape sequence: "\q""#
+            )
+        );
+    }
+
+    #[test]
+    fn parse_raw_strings() {
+        let mut pear = Pear::from(r##"r"C:\no\escapes""##);
+        assert_eq!(
+            Literal::try_from(&mut pear),
+            Ok(Literal::String(r"C:\no\escapes".to_string()))
+        );
+
+        let mut pear = Pear::from("r#\"spans\n\"two\" lines\"#");
+        assert_eq!(
+            Literal::try_from(&mut pear),
+            Ok(Literal::String("spans\n\"two\" lines".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_chars() {
+        assert_eq!(parse("'a'"), Ok(Literal::Char('a')));
+        assert_eq!(parse(r"'\n'"), Ok(Literal::Char('\n')));
+        assert_eq!(parse(r"'\u{1F600}'"), Ok(Literal::Char('😀')));
+        assert_eq!(parse(r"'\''"), Ok(Literal::Char('\'')));
+        assert_eq!(parse("'\"'"), Ok(Literal::Char('"')));
+        assert_eq!(parse("'é'"), Ok(Literal::Char('é')));
+    }
+
+    #[test]
+    fn parse_bad_chars() {
+        let char = TokenKind::CharLiteral;
+        let one_char = r#"Char literals must contain exactly one character, use "..." for strings"#;
+        assert_eq!(
+            parse("''"),
+            invalid(char, "''", Span::new(0, 2, 1, 1), "Empty char literal")
+        );
+        assert_eq!(
+            parse("  'ab'"),
+            invalid(char, "'ab'", Span::new(2, 6, 1, 3), one_char)
+        );
+        assert_eq!(
+            parse(r"'\n\t'"),
+            invalid(char, r"'\n\t'", Span::new(0, 6, 1, 1), one_char)
+        );
+        assert_eq!(
+            parse("'a"),
+            invalid(
+                TokenKind::UnterminatedChar,
+                "'a",
+                Span::new(0, 2, 1, 1),
+                "Unterminated char literal, missing closing quote"
+            )
+        );
+        assert_eq!(
+            parse(r"'\q'"),
+            invalid(
+                char,
+                r"'\q'",
+                Span::new(0, 4, 1, 1),
+                r#"Unknown escape sequence: "\q""#
+            )
+        );
+    }
+
+    #[test]
+    fn parse_byte_strings() {
+        assert_eq!(
+            parse(r#"b"hi\n\u{7f}""#),
+            Ok(Literal::Bytes(b"hi\n\x7f".to_vec()))
+        );
+        assert_eq!(parse(r#"b"""#), Ok(Literal::Bytes(vec![])));
+        let ascii_only = r#"Byte strings can only contain ASCII, found "é""#;
+        assert_eq!(
+            parse(r#"b"caf\u{e9}""#),
+            invalid(
+                TokenKind::ByteStringLiteral,
+                r#"b"caf\u{e9}""#,
+                Span::new(0, 12, 1, 1),
+                ascii_only
+            )
+        );
+        assert_eq!(
+            parse(r#"b"é""#),
+            invalid(
+                TokenKind::ByteStringLiteral,
+                r#"b"é""#,
+                Span::new(0, 5, 1, 1),
+                ascii_only
+            )
+        );
+    }
+
+    #[test]
+    fn parse_unterminated_string() {
+        assert_eq!(
+            parse("\n  \"never closed;"),
+            invalid(
+                TokenKind::UnterminatedString,
+                "\"never closed;",
+                Span::new(3, 17, 2, 3),
+                "Unterminated string literal, missing closing quote"
+            )
+        );
+    }
+}
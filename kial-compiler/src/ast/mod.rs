@@ -1,8 +1,10 @@
-mod binary_operation;
+pub(crate) mod binary_operation;
 mod block;
-mod expression;
+pub(crate) mod expression;
 mod function;
 mod identifier;
+mod interpolation;
 mod literal;
-mod module;
-mod statement;
+pub(crate) mod module;
+pub(crate) mod statement;
+mod unary_operation;
@@ -1,14 +1,315 @@
-use crate::ast::function::{FunctionDefinition, FunctionInvocation};
-use crate::tokenstream::TokenStream;
+use crate::ast::function::FunctionDefinition;
+use crate::env::Env;
+use crate::lexer::{Span, TokenKind};
+use crate::pear::error::{ParseError, ParseErrorKind};
+use crate::pear::Pear;
+use crate::trace;
+use crate::val::Val;
+use std::collections::HashMap;
 
-// TODO: WIP
-pub(crate) struct Module {
-    entry: Option<FunctionDefinition>,
-    functions: Vec<FunctionInvocation>,
+/// A whole source file: every top-level item, with `main` kept apart as the entry point
+#[derive(Debug, PartialEq)]
+pub struct Module {
+    pub(crate) entry: Option<FunctionDefinition>,
+    pub(crate) functions: Vec<FunctionDefinition>,
 }
 
 impl Module {
-    fn parse(ts: TokenStream) -> Self {
-        todo!()
+    /// Whether the module defines a `main` function
+    pub fn has_entry(&self) -> bool {
+        self.entry.is_some()
+    }
+
+    /// Names of the top-level functions other than `main`, in source order
+    pub fn function_names(&self) -> impl Iterator<Item = &str> {
+        self.functions
+            .iter()
+            .map(|function| function.name.0.as_str())
+    }
+
+    /// Evaluates the body of `main`, returning the value it ends with
+    pub fn run(&self) -> Result<Val, String> {
+        let entry = self
+            .entry
+            .as_ref()
+            .ok_or("The module has no main function")?;
+        entry.body.eval(&Env::default())
+    }
+}
+
+impl TryFrom<&mut Pear<'_>> for Module {
+    type Error = ParseError;
+
+    fn try_from(pear: &mut Pear<'_>) -> Result<Self, Self::Error> {
+        let _trace = trace::enter("Module", pear);
+        let mut entry = None;
+        let mut functions = vec![];
+        let mut names: HashMap<String, Span> = HashMap::new();
+
+        while let Some(next) = pear.peek_next() {
+            // Constants and types will be more kinds of items
+            if next.kind != TokenKind::Func {
+                let error = pear.unexpected([TokenKind::Func]);
+                pear.tag(next.kind)?; // Skip it, as it might be a stray "}"
+                pear.recover(error);
+                continue;
+            }
+
+            // The name comes right after "func"
+            let name_token = pear.peek_n(2);
+            let span = name_token.map_or(next.span, |name| name.span);
+            let function = match FunctionDefinition::try_from(&mut *pear) {
+                Ok(function) => function,
+                Err(error) => {
+                    pear.recover(error);
+                    continue;
+                }
+            };
+
+            let name = function.name.0.clone();
+            if let Some(first) = names.get(&name) {
+                pear.report(
+                    ParseError::new(ParseErrorKind::Duplicate, span)
+                        .found(name_token)
+                        .note(format!("First defined at {first}")),
+                );
+                continue;
+            }
+            names.insert(name.clone(), span);
+
+            if name == "main" {
+                entry = Some(function);
+            } else {
+                functions.push(function);
+            }
+        }
+
+        Ok(Self { entry, functions })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer::{LexErrorKind, Span, TokenKind};
+    use crate::parse_module;
+    use crate::{Found, ParseErrorKind, Val};
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn parse_empty_module() {
+        let module = parse_module("").unwrap();
+        assert!(!module.has_entry());
+        assert_eq!(module.function_names().count(), 0);
+
+        let module = parse_module("// nothing here\n").unwrap();
+        assert!(!module.has_entry());
+    }
+
+    #[test]
+    fn parse_module_with_entry() {
+        let src = "\
+func add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+func main() {
+    let x = add(1, 2);
+}
+
+func unused() {}
+";
+        let module = parse_module(src).unwrap();
+        assert!(module.has_entry());
+        assert_eq!(module.entry.as_ref().unwrap().name.0, "main");
+        assert_eq!(
+            module.function_names().collect::<Vec<_>>(),
+            vec!["add", "unused"]
+        );
+    }
+
+    #[test]
+    fn parse_module_without_entry() {
+        let module = parse_module("func f() {} func g() {}").unwrap();
+        assert!(!module.has_entry());
+        assert_eq!(module.function_names().collect::<Vec<_>>(), vec!["f", "g"]);
+    }
+
+    #[test]
+    fn run_main() {
+        let src = "\
+func main() {
+    let a = 1;
+    let b = { a + 1 };
+    a + b
+}
+";
+        assert_eq!(parse_module(src).unwrap().run(), Ok(Val::Int(3)));
+        assert_eq!(parse_module("func main() {}").unwrap().run(), Ok(Val::Unit));
+        assert_eq!(
+            parse_module("func f() { 1 }").unwrap().run(),
+            Err("The module has no main function".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_expression_statements() {
+        let src = "\
+func main() {
+    f(1);
+    let a = 1;
+    a + 1;
+    a
+}
+";
+        let module = parse_module(src).unwrap();
+        assert!(module.has_entry());
+        assert!(parse_module("func main() { f(1); }").is_ok());
+        assert!(parse_module("func main() { let a = 1; a + 1; }").is_ok());
+    }
+
+    #[test]
+    fn reject_duplicate_names() {
+        let errors = parse_module("func f() {}\nfunc g() {}\nfunc  f(a) {}").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind, ParseErrorKind::Duplicate);
+        assert_eq!(errors[0].span, Span::new(30, 31, 3, 7));
+        assert_eq!(
+            errors[0].found,
+            Some(Found {
+                kind: TokenKind::Ident,
+                text: "f".to_string()
+            })
+        );
+        assert_eq!(errors[0].notes, vec!["First defined at 1:6".to_string()]);
+
+        let errors = parse_module("func main() {}\nfunc main() {}").unwrap_err();
+        assert_eq!(errors[0].kind, ParseErrorKind::Duplicate);
+        assert_eq!(errors[0].notes, vec!["First defined at 1:6".to_string()]);
+    }
+
+    #[test]
+    fn reject_non_items() {
+        let errors = parse_module("func f() {}\nlet a = 1;").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind, ParseErrorKind::UnexpectedToken);
+        assert_eq!(errors[0].span, Span::new(12, 15, 2, 1));
+        assert_eq!(errors[0].expected, BTreeSet::from([TokenKind::Func]));
+        assert_eq!(
+            errors[0].found.as_ref().map(|found| found.kind),
+            Some(TokenKind::Let)
+        );
+
+        let errors = parse_module("func f() {\n").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind, ParseErrorKind::Unclosed);
+        assert_eq!(errors[0].span, Span::new(9, 10, 1, 10));
+        assert_eq!(errors[0].expected, BTreeSet::from([TokenKind::CloseBrace]));
+        assert_eq!(errors[0].found, None);
+    }
+
+    #[test]
+    fn report_lexical_errors() {
+        let kinds = |src| {
+            let errors = parse_module(src).unwrap_err();
+            errors.iter().map(|error| error.kind).collect::<Vec<_>>()
+        };
+
+        // The parser fails on the same tokens, but only the lexer's errors are reported
+        let errors = parse_module("func main() { 1 @ 2 }").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].kind,
+            ParseErrorKind::Lexical(LexErrorKind::UnknownChar)
+        );
+        assert_eq!(errors[0].span, Span::new(16, 17, 1, 17));
+        assert_eq!(errors[0].to_string(), r#"1:17: Unknown character: "@""#);
+        assert_eq!(
+            kinds(r#"func main() { "a\qb" }"#),
+            vec![ParseErrorKind::Lexical(LexErrorKind::InvalidEscape)]
+        );
+        assert_eq!(
+            kinds("func main() {\n    'a\n}"),
+            vec![ParseErrorKind::Lexical(LexErrorKind::UnterminatedChar)]
+        );
+
+        // Errors elsewhere are still reported
+        assert_eq!(
+            kinds("func main() { 1 @ 2; let = 3; }"),
+            vec![
+                ParseErrorKind::Lexical(LexErrorKind::UnknownChar),
+                ParseErrorKind::UnexpectedToken
+            ]
+        );
+    }
+
+    #[test]
+    fn report_missing_semis() {
+        let errors = parse_module("func main() {\n    1 2\n}").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].to_string(),
+            r#"2:7: Expected "}" or ";", found "2""#
+        );
+
+        // "1.5e" is lexed as "1.5" followed by "e"
+        let errors = parse_module("func main() { 1.5e }").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind, ParseErrorKind::UnexpectedToken);
+        assert_eq!(errors[0].span, Span::new(17, 18, 1, 18));
+    }
+
+    #[test]
+    fn report_unclosed_interpolation() {
+        let src = "\
+func main() {
+    let a = \"{\";
+    let b = 1;
+}
+";
+        let errors = parse_module(src).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].to_string(),
+            r#"2:14: Unclosed interpolation, missing "}", write "\{" for a literal brace: "{"""#
+        );
+    }
+
+    #[test]
+    fn report_every_error() {
+        let src = "\
+func f(a b) {
+    let x = ;
+}
+
+func g() {
+    let y = 1 +;
+    y
+}
+}
+func h() {
+    let z = 2
+}
+
+func g() {}
+func k() {
+";
+        let errors = parse_module(src).unwrap_err();
+        let summary = errors
+            .iter()
+            .map(|error| (error.kind, error.span.line, error.span.column))
+            .collect::<Vec<_>>();
+
+        // The error in the body of f is skipped along with it, after the error in its parameters
+        assert_eq!(
+            summary,
+            vec![
+                (ParseErrorKind::UnexpectedToken, 1, 10),
+                (ParseErrorKind::MissingExpression, 6, 16),
+                (ParseErrorKind::UnexpectedToken, 9, 1),
+                (ParseErrorKind::UnexpectedToken, 12, 1),
+                (ParseErrorKind::Duplicate, 14, 6),
+                (ParseErrorKind::Unclosed, 15, 10),
+            ]
+        );
     }
 }
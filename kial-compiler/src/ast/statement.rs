@@ -1,7 +1,11 @@
 use crate::ast::expression::Expr;
 use crate::ast::statement::assignment::Assignment;
-use crate::lexer::TokenKind;
+use crate::env::Env;
+use crate::lexer::{Span, TokenKind};
+use crate::pear::error::{ParseError, ParseErrorKind};
 use crate::pear::Pear;
+use crate::trace;
+use crate::val::Val;
 use binding::Binding;
 
 mod assignment;
@@ -12,17 +16,38 @@ pub(crate) enum Stmt {
     Assignment(Assignment),
     Binding(Binding),
     Expr(Expr),
+    /// An expression followed by ";", evaluated only for its effects
+    Semi(Expr),
+    /// A statement that failed to parse, covering the tokens skipped to recover from it
+    Error(Span),
+}
+
+impl Stmt {
+    pub(crate) fn eval(&self, env: &mut Env) -> Result<Val, String> {
+        match self {
+            Stmt::Assignment(assignment) => assignment.eval(env),
+            Stmt::Binding(binding) => binding.eval(env),
+            Stmt::Expr(expr) => expr.eval(env),
+            Stmt::Semi(expr) => {
+                expr.eval(env)?;
+                Ok(Val::Unit)
+            }
+            Stmt::Error(span) => Err(format!("{span}: Can't evaluate a statement with errors")),
+        }
+    }
 }
 
 impl TryFrom<&mut Pear<'_>> for Stmt {
-    type Error = String;
+    type Error = ParseError;
 
-    fn try_from(pear: &mut Pear<'_>) -> Result<Self, Self::Error> {
+    fn try_from(pear: &mut Pear<'_>) -> Result<Self, ParseError> {
+        let _trace = trace::enter("Stmt", pear);
         let Some(next) = pear.peek_next() else {
-            return Err("Expected token, found None".to_string());
+            return Err(pear.error(ParseErrorKind::MissingExpression));
         };
 
-        if let Ok(var) = Binding::try_from(&mut *pear) {
+        if next.kind == TokenKind::Let {
+            let var = Binding::try_from(&mut *pear)?;
             pear.tag(TokenKind::Semi)?;
             return Ok(Stmt::Binding(var));
         }
@@ -31,29 +56,42 @@ impl TryFrom<&mut Pear<'_>> for Stmt {
         let is_assignment = next.kind == TokenKind::Ident
             && next_next.is_some_and(|token| token.kind == TokenKind::Equals);
         if is_assignment {
-            if let Ok(assign) = Assignment::try_from(&mut *pear) {
-                pear.tag(TokenKind::Semi)?;
-                return Ok(Stmt::Assignment(assign));
-            }
+            let assign = Assignment::try_from(&mut *pear)?;
+            pear.tag(TokenKind::Semi)?;
+            return Ok(Stmt::Assignment(assign));
         }
 
-        if let Ok(expr) = Expr::try_from(&mut *pear) {
-            return Ok(Self::Expr(expr));
+        let expr = Expr::try_from(&mut *pear)?;
+        if pear.next_is(TokenKind::Semi) {
+            pear.tag(TokenKind::Semi)?;
+            return Ok(Self::Semi(expr));
+        }
+
+        // Only the last statement of a block can leave out the ";", to be the block's value
+        let is_last = pear
+            .peek_next()
+            .is_none_or(|token| token.kind == TokenKind::CloseBrace);
+        if !is_last {
+            return Err(pear.unexpected([TokenKind::Semi, TokenKind::CloseBrace]));
         }
 
-        Err("Malformed statement".to_string())
+        Ok(Self::Expr(expr))
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::ast::binary_operation::BinOp;
     use crate::ast::expression::Expr;
     use crate::ast::identifier::Ident;
-    use crate::ast::literal::Literal;
+    use crate::ast::literal::{IntLiteral, Literal, Radix};
     use crate::ast::statement::assignment::Assignment;
     use crate::ast::statement::binding::{Binding, BindingUsage, Declaration, Initialization};
     use crate::ast::statement::Stmt;
+    use crate::lexer::{Span, TokenKind};
+    use crate::pear::error::ParseErrorKind;
     use crate::pear::Pear;
+    use std::collections::BTreeSet;
 
     #[test]
     fn statement_declaration() {
@@ -77,7 +115,46 @@ mod tests {
             stmt,
             Stmt::Binding(Binding::Initialization(Initialization {
                 name: Ident("b".to_string()),
-                value: Expr::Literal(Literal::Number(25)),
+                value: Expr::Literal(Literal::Int(IntLiteral::new(25, Radix::Decimal))),
+            }))
+        );
+    }
+
+    #[test]
+    fn statement_initialization_with_binary_expression() {
+        let mut pear = Pear::from("let b = 1 + 2 * 3; b");
+        let stmt = Stmt::try_from(&mut pear).unwrap();
+
+        let int = |value| {
+            Box::new(Expr::Literal(Literal::Int(IntLiteral::new(
+                value,
+                Radix::Decimal,
+            ))))
+        };
+        assert_eq!(
+            stmt,
+            Stmt::Binding(Binding::Initialization(Initialization {
+                name: Ident("b".to_string()),
+                value: Expr::Binary(
+                    BinOp::Add,
+                    int(1),
+                    Box::new(Expr::Binary(BinOp::Mul, int(2), int(3)))
+                ),
+            }))
+        );
+        assert!(Stmt::try_from(&mut pear).is_ok());
+    }
+
+    #[test]
+    fn statement_with_comments() {
+        let mut pear = Pear::from("/// The answer\nlet b = /* inline */ 25; // trailing");
+        let stmt = Stmt::try_from(&mut pear).unwrap();
+
+        assert_eq!(
+            stmt,
+            Stmt::Binding(Binding::Initialization(Initialization {
+                name: Ident("b".to_string()),
+                value: Expr::Literal(Literal::Int(IntLiteral::new(25, Radix::Decimal))),
             }))
         );
     }
@@ -91,11 +168,50 @@ mod tests {
             stmt,
             Stmt::Assignment(Assignment {
                 name: Ident("c".to_string()),
-                value: Expr::Literal(Literal::String("\"Anything else\"".to_string())),
+                value: Expr::Literal(Literal::String("Anything else".to_string())),
             })
         );
     }
 
+    #[test]
+    fn statement_expression_with_semi() {
+        let mut pear = Pear::from("a + 1; a");
+        let stmt = Stmt::try_from(&mut pear).unwrap();
+
+        let usage = || {
+            Box::new(Expr::BindingUsage(BindingUsage {
+                name: Ident("a".to_string()),
+            }))
+        };
+        assert_eq!(
+            stmt,
+            Stmt::Semi(Expr::Binary(
+                BinOp::Add,
+                usage(),
+                Box::new(Expr::Literal(Literal::Int(IntLiteral::new(
+                    1,
+                    Radix::Decimal
+                ))))
+            ))
+        );
+        assert_eq!(Stmt::try_from(&mut pear), Ok(Stmt::Expr(*usage())));
+    }
+
+    #[test]
+    fn statement_expression_needs_semi() {
+        let error = Stmt::try_from(&mut Pear::from("1 2 }")).unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::UnexpectedToken);
+        assert_eq!(error.span, Span::new(2, 3, 1, 3));
+        assert_eq!(
+            error.expected,
+            BTreeSet::from([TokenKind::Semi, TokenKind::CloseBrace])
+        );
+
+        // The last expression of a block doesn't need one
+        assert!(Stmt::try_from(&mut Pear::from("1 }")).is_ok());
+        assert!(Stmt::try_from(&mut Pear::from("1")).is_ok());
+    }
+
     #[test]
     fn statement_binding_usage() {
         let mut pear = Pear::from("c");
@@ -1,7 +1,11 @@
 use crate::ast::expression::Expr;
 use crate::ast::identifier::Ident;
+use crate::env::Env;
 use crate::lexer::TokenKind;
+use crate::pear::error::ParseError;
 use crate::pear::Pear;
+use crate::trace;
+use crate::val::Val;
 
 #[derive(Debug, PartialEq)]
 pub(crate) struct Assignment {
@@ -9,9 +13,19 @@ pub(crate) struct Assignment {
     pub(crate) value: Expr,
 }
 
+impl Assignment {
+    /// Updates the binding where it was defined, which may be a block enclosing this one
+    pub(crate) fn eval(&self, env: &mut Env) -> Result<Val, String> {
+        let val = self.value.eval(env)?;
+        env.assign_binding(&self.name.0, val)?;
+        Ok(Val::Unit)
+    }
+}
+
 impl TryFrom<&mut Pear<'_>> for Assignment {
-    type Error = String;
+    type Error = ParseError;
     fn try_from(pear: &mut Pear<'_>) -> Result<Self, Self::Error> {
+        let _trace = trace::enter("Assignment", pear);
         let name = Ident::try_from(&mut *pear)?;
         pear.tag(TokenKind::Equals)?;
         let value = Expr::try_from(&mut *pear)?;
@@ -23,7 +37,7 @@ impl TryFrom<&mut Pear<'_>> for Assignment {
 mod test {
     use crate::ast::expression::Expr;
     use crate::ast::identifier::Ident;
-    use crate::ast::literal::Literal;
+    use crate::ast::literal::{IntLiteral, Literal, Radix};
     use crate::ast::statement::assignment::Assignment;
     use crate::pear::Pear;
 
@@ -35,7 +49,7 @@ mod test {
             local,
             Assignment {
                 name: Ident("c".to_string()),
-                value: Expr::Literal(Literal::Number(30))
+                value: Expr::Literal(Literal::Int(IntLiteral::new(30, Radix::Decimal)))
             }
         );
     }
@@ -48,7 +62,7 @@ mod test {
             local,
             Assignment {
                 name: Ident("c".to_string()),
-                value: Expr::Literal(Literal::Number(30))
+                value: Expr::Literal(Literal::Int(IntLiteral::new(30, Radix::Decimal)))
             }
         );
     }
@@ -1,7 +1,11 @@
 use crate::ast::expression::Expr;
 use crate::ast::identifier::Ident;
+use crate::env::Env;
 use crate::lexer::TokenKind;
+use crate::pear::error::ParseError;
 use crate::pear::Pear;
+use crate::trace;
+use crate::val::Val;
 
 #[derive(Debug, PartialEq)]
 pub(crate) struct Declaration {
@@ -9,9 +13,10 @@ pub(crate) struct Declaration {
 }
 
 impl TryFrom<&mut Pear<'_>> for Declaration {
-    type Error = String;
+    type Error = ParseError;
 
     fn try_from(pear: &mut Pear) -> Result<Self, Self::Error> {
+        let _trace = trace::enter("Declaration", pear);
         pear.tag(TokenKind::Let)?;
         let name = Ident::try_from(&mut *pear)?; // Have to explicit re-borrow here https://quinedot.github.io/rust-learning/st-reborrow.html
         pear.tag(TokenKind::Semi)?;
@@ -26,8 +31,9 @@ pub(crate) struct Initialization {
 }
 
 impl TryFrom<&mut Pear<'_>> for Initialization {
-    type Error = String;
+    type Error = ParseError;
     fn try_from(pear: &mut Pear<'_>) -> Result<Self, Self::Error> {
+        let _trace = trace::enter("Initialization", pear);
         pear.tag(TokenKind::Let)?;
         let name = Ident::try_from(&mut *pear)?;
         pear.tag(TokenKind::Equals)?;
@@ -43,19 +49,37 @@ pub(crate) enum Binding {
     Initialization(Initialization),
 }
 
+impl Binding {
+    pub(crate) fn eval(&self, env: &mut Env) -> Result<Val, String> {
+        match self {
+            Binding::Declaration(Declaration { name }) => {
+                env.store_binding(name.0.clone(), Val::Unit);
+            }
+            Binding::Initialization(Initialization { name, value }) => {
+                let val = value.eval(env)?;
+                env.store_binding(name.0.clone(), val);
+            }
+        }
+
+        Ok(Val::Unit)
+    }
+}
+
 impl TryFrom<&mut Pear<'_>> for Binding {
-    type Error = String;
+    type Error = ParseError;
 
     fn try_from(pear: &mut Pear<'_>) -> Result<Self, Self::Error> {
+        let _trace = trace::enter("Binding", pear);
         pear.tag(TokenKind::Let)?;
         let name = Ident::try_from(&mut *pear)?;
-        if pear
-            .peek_next()
-            .is_some_and(|token| token.kind == TokenKind::Semi)
-        {
+        if pear.next_is(TokenKind::Semi) {
             return Ok(Self::Declaration(Declaration { name }));
         }
 
+        if !pear.next_is(TokenKind::Equals) {
+            return Err(pear.unexpected([TokenKind::Equals, TokenKind::Semi]));
+        }
+
         pear.tag(TokenKind::Equals)?;
         let value = Expr::try_from(&mut *pear)?;
         Ok(Self::Initialization(Initialization { name, value }))
@@ -67,11 +91,56 @@ pub(crate) struct BindingUsage {
     pub(crate) name: Ident,
 }
 
+impl BindingUsage {
+    pub(crate) fn eval(&self, env: &Env) -> Result<Val, String> {
+        env.get_binding(&self.name.0)
+    }
+}
+
 impl TryFrom<&mut Pear<'_>> for BindingUsage {
-    type Error = String;
+    type Error = ParseError;
 
     fn try_from(pear: &mut Pear<'_>) -> Result<Self, Self::Error> {
+        let _trace = trace::enter("BindingUsage", pear);
         let name = Ident::try_from(&mut *pear)?;
         Ok(Self { name })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::statement::binding::Binding;
+    use crate::lexer::{Span, TokenKind};
+    use crate::pear::error::{Found, ParseErrorKind};
+    use crate::pear::Pear;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn binding_to_reserved_keyword() {
+        let error = Binding::try_from(&mut Pear::from("let while = 10;")).unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::ReservedKeyword);
+        assert_eq!(error.span, Span::new(4, 9, 1, 5));
+        assert_eq!(
+            error.found,
+            Some(Found {
+                kind: TokenKind::While,
+                text: "while".to_string()
+            })
+        );
+
+        let error = Binding::try_from(&mut Pear::from("let\n  true;")).unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::ReservedKeyword);
+        assert_eq!(error.span, Span::new(6, 10, 2, 3));
+    }
+
+    #[test]
+    fn binding_without_value() {
+        let error = Binding::try_from(&mut Pear::from("let a 10;")).unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::UnexpectedToken);
+        assert_eq!(
+            error.expected,
+            BTreeSet::from([TokenKind::Equals, TokenKind::Semi])
+        );
+        assert_eq!(error.span, Span::new(6, 8, 1, 7));
+    }
+}
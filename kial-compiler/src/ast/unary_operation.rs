@@ -0,0 +1,41 @@
+use crate::lexer::TokenKind;
+use crate::val::Val;
+
+/// Prefix operators. They bind tighter than every binary operator but "**", so -a * b is (-a) * b
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub(crate) enum UnaryOp {
+    Neg,  // -
+    Not,  // !
+    Plus, // +
+}
+
+impl UnaryOp {
+    pub(crate) fn eval(&self, operand: Val) -> Result<Val, String> {
+        let unsupported = || format!("Unsupported operation, operand: {operand:?} | op: {self:?}");
+
+        match (self, &operand) {
+            (UnaryOp::Neg, Val::Int(int)) => int
+                .checked_neg()
+                .map(Val::Int)
+                .ok_or_else(|| format!("Integer overflow in -{int}")),
+            (UnaryOp::Neg, Val::Float(float)) => Ok(Val::Float(-float)),
+            (UnaryOp::Not, Val::Int(int)) => Ok(Val::Int(!int)),
+            (UnaryOp::Not, Val::Bool(bool)) => Ok(Val::Bool(!bool)),
+            (UnaryOp::Plus, Val::Int(_) | Val::Float(_)) => Ok(operand.clone()),
+            _ => Err(unsupported()),
+        }
+    }
+}
+
+impl TryFrom<TokenKind> for UnaryOp {
+    type Error = String;
+
+    fn try_from(kind: TokenKind) -> Result<Self, Self::Error> {
+        match kind {
+            TokenKind::Minus => Ok(UnaryOp::Neg),
+            TokenKind::Bang => Ok(UnaryOp::Not),
+            TokenKind::Plus => Ok(UnaryOp::Plus),
+            _ => Err(format!("\"{kind:?}\" is not a unary operator")),
+        }
+    }
+}
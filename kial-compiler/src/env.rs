@@ -0,0 +1,48 @@
+use crate::val::Val;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub(crate) struct Env<'parent> {
+    // Shared with child scopes, which can assign to bindings they didn't define
+    bindings: RefCell<HashMap<String, Val>>,
+    parent: Option<&'parent Self>,
+}
+
+impl<'parent> Env<'parent> {
+    pub(crate) fn store_binding(&mut self, name: String, val: Val) {
+        self.bindings.get_mut().insert(name, val);
+    }
+
+    /// Replaces the value of `name` in the scope that defined it
+    pub(crate) fn assign_binding(&self, name: &str, val: Val) -> Result<(), String> {
+        if let Some(binding) = self.bindings.borrow_mut().get_mut(name) {
+            *binding = val;
+            return Ok(());
+        }
+
+        match self.parent {
+            Some(parent) => parent.assign_binding(name, val),
+            None => Err(format!("Binding does not exist: {name}")),
+        }
+    }
+
+    pub(crate) fn get_binding(&self, name: &str) -> Result<Val, String> {
+        self.get_binding_no_error(name)
+            .ok_or_else(|| format!("Binding does not exist: {name}"))
+    }
+
+    fn get_binding_no_error(&self, name: &str) -> Option<Val> {
+        self.bindings.borrow().get(name).cloned().or_else(|| {
+            self.parent
+                .and_then(|parent| parent.get_binding_no_error(name))
+        })
+    }
+
+    pub(crate) fn create_child(&'parent self) -> Self {
+        Self {
+            bindings: RefCell::default(),
+            parent: Some(self),
+        }
+    }
+}
@@ -1,140 +1,612 @@
-#![allow(unused)]
-
-use std::any::Any;
-use std::collections::{HashMap, VecDeque};
 use std::fmt::{Display, Formatter};
-use std::path::Iter;
+use std::ops::Range;
 use std::str::Chars;
 
+use unicode_xid::UnicodeXID;
+
+use crate::trace;
+
 use self::TokenKind::*;
 
+pub mod incremental;
+
 const EOF_CHAR: char = '\0';
 
+/// Every reserved word, along with the token it is lexed as
+const KEYWORDS: [(&str, TokenKind); 19] = [
+    ("let", Let),
+    ("func", Func),
+    ("true", True),
+    ("false", False),
+    ("if", If),
+    ("else", Else),
+    ("while", While),
+    ("loop", Loop),
+    ("for", For),
+    ("in", In),
+    ("return", Return),
+    ("break", Break),
+    ("continue", Continue),
+    ("mut", Mut),
+    ("const", Const),
+    ("struct", Struct),
+    ("enum", Enum),
+    ("match", Match),
+    ("use", Use),
+];
+
+/// Identifiers follow Unicode's XID_Start/XID_Continue rules, with '_' also allowed at the start
 fn is_valid_id_start(c: char) -> bool {
-    c == '_' || c.is_alphabetic()
+    c == '_' || c.is_xid_start()
+}
+
+fn is_valid_id_continue(c: char) -> bool {
+    c.is_xid_continue()
 }
 
 struct Cursor<'a> {
+    src: &'a str,
     chars: Chars<'a>,
     len: usize,
+    src_len: usize,
+    line: usize,
+    column: usize,
+    token_line: usize,
+    token_column: usize,
+    errors: Vec<LexError>,
+    /// Interpolated strings we are currently inside the braces of, innermost last
+    interpolations: Vec<Interpolation>,
+}
+
+/// An open "...{" string, whose interpolation ends at the '}' that brings `depth` below zero
+struct Interpolation {
+    quote: Span,
+    /// The '{' that started the expression being lexed
+    brace: Span,
+    depth: usize,
+}
+
+/// Where a string literal, or the part of one after an interpolation, stops
+enum StringStop {
+    Quote,
+    OpenBrace(Span),
+    Eof,
 }
 
 impl<'a> Cursor<'a> {
     fn new(input: &'a str) -> Cursor<'a> {
+        Self::at(input, 0, 1, 1)
+    }
+
+    /// Starts lexing `input` from byte `offset`, which is at `line`:`column`
+    fn at(input: &'a str, offset: usize, line: usize, column: usize) -> Cursor<'a> {
         Self {
-            chars: input.chars(),
-            len: input.len(),
+            src: input,
+            chars: input[offset..].chars(),
+            len: input.len() - offset,
+            src_len: input.len(),
+            line,
+            column,
+            token_line: line,
+            token_column: column,
+            errors: vec![],
+            interpolations: vec![],
         }
     }
 
     fn first(&mut self) -> char {
         self.chars.clone().next().unwrap_or(EOF_CHAR)
     }
-    fn shrink_1(&mut self) {
-        self.len -= 1;
+
+    fn second(&mut self) -> char {
+        let mut chars = self.chars.clone();
+        chars.next();
+        chars.next().unwrap_or(EOF_CHAR)
+    }
+
+    fn third(&mut self) -> char {
+        let mut chars = self.chars.clone();
+        chars.next();
+        chars.next();
+        chars.next().unwrap_or(EOF_CHAR)
+    }
+
+    fn is_eof(&self) -> bool {
+        self.chars.as_str().is_empty()
     }
 
     fn pos(&self) -> usize {
         self.len - self.chars.as_str().len()
     }
 
+    /// Byte offset of the start of the token currently being lexed
+    fn token_start(&self) -> usize {
+        self.src_len - self.len
+    }
+
+    /// Byte offset of the cursor in `src`
+    fn offset(&self) -> usize {
+        self.src_len - self.chars.as_str().len()
+    }
+
+    /// The source text from byte `start` up to the cursor
+    fn slice_since(&self, start: usize) -> &'a str {
+        &self.src[start..self.offset()]
+    }
+
+    /// Empty span at the cursor's current position, to be extended with `span_since()`
+    fn mark(&self) -> Span {
+        let offset = self.offset();
+        Span::new(offset, offset, self.line, self.column)
+    }
+
+    fn span_since(&self, mark: Span) -> Span {
+        Span {
+            end: self.mark().start,
+            ..mark
+        }
+    }
+
+    /// Span of the first `len` bytes of the token currently being lexed
+    fn token_prefix_span(&self, len: usize) -> Span {
+        let start = self.token_start();
+        Span::new(start, start + len, self.token_line, self.token_column)
+    }
+
+    fn error(&mut self, kind: LexErrorKind, span: Span, text: impl Into<String>) {
+        self.errors.push(LexError {
+            kind,
+            span,
+            text: text.into(),
+        });
+    }
+
     fn pos_reset(&mut self) {
         self.len = self.chars.as_str().len();
+        self.token_line = self.line;
+        self.token_column = self.column;
     }
 
     fn take_1(&mut self) -> char {
-        let c = self.chars.next().unwrap_or(EOF_CHAR);
+        let Some(c) = self.chars.next() else {
+            return EOF_CHAR;
+        };
+
+        match c {
+            '\n' => {
+                self.line += 1;
+                self.column = 1;
+            }
+            // "\r\n" is a single line break, so the '\r' doesn't take up a column
+            '\r' if self.first() == '\n' => {}
+            _ => self.column += 1,
+        }
 
         c
     }
 
-    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> String {
-        let mut s = vec![];
-        while pred(self.first()) {
-            s.push(self.take_1());
+    /// Takes the next char as the second half of a two-char token
+    fn take_1_as(&mut self, kind: TokenKind) -> TokenKind {
+        self.take_1();
+        kind
+    }
+
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> &'a str {
+        let start = self.offset();
+        while !self.is_eof() && pred(self.first()) {
+            self.take_1();
+        }
+
+        self.slice_since(start)
+    }
+
+    fn extract_ident(&mut self) -> &'a str {
+        self.take_while(is_valid_id_continue)
+    }
+
+    /// Takes the rest of a numeric literal after its first digit. Integers can be written in
+    /// decimal, hex (0xff), octal (0o17) or binary (0b1010), and floats as 1.5 or 2e10. Digits
+    /// can be separated with underscores. Digits that are invalid for the radix are left for the
+    /// parser to report.
+    fn extract_num(&mut self, first_digit: char) -> TokenKind {
+        let is_digit = |c: char| c.is_ascii_digit() || c == '_';
+
+        if first_digit == '0' && matches!(self.first(), 'x' | 'o' | 'b') {
+            self.take_1();
+            self.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+            return NumericLiteral;
+        }
+
+        self.take_while(is_digit);
+
+        let mut kind = NumericLiteral;
+        // The digit check keeps "1.foo" and "1..2" from being floats
+        if self.first() == '.' && self.second().is_ascii_digit() {
+            self.take_1();
+            self.take_while(is_digit);
+            kind = FloatLiteral;
         }
 
-        s.iter().collect()
+        let has_exponent = matches!(self.first(), 'e' | 'E')
+            && (self.second().is_ascii_digit()
+                || (matches!(self.second(), '+' | '-') && self.third().is_ascii_digit()));
+        if has_exponent {
+            self.take_1();
+            self.take_1();
+            self.take_while(is_digit);
+            kind = FloatLiteral;
+        }
+
+        kind
     }
 
-    fn extract_ident(&mut self) -> String {
-        self.take_while(|c| c.is_alphanumeric())
+    /// Takes the rest of a string literal, up to and including the closing quote or, if
+    /// `interpolate` is set, the '{' of an interpolation. A literal brace is written "\{". The
+    /// opening quote (or the '}' closing the previous interpolation) is expected to have been
+    /// taken already. Escape sequences are checked but not decoded.
+    fn extract_double_quoted_string(&mut self, interpolate: bool) -> StringStop {
+        while !self.is_eof() {
+            let mark = self.mark();
+            match self.take_1() {
+                '"' => return StringStop::Quote,
+                '{' if interpolate => return StringStop::OpenBrace(self.span_since(mark)),
+                '\\' => self.check_escape(mark),
+                _ => {}
+            }
+        }
+
+        StringStop::Eof
     }
 
-    fn extract_num(&mut self) -> String {
-        self.take_while(|c| c.is_numeric())
+    /// Takes the rest of a char literal, up to and including the closing quote. Char literals
+    /// don't span lines, so returns false if a newline comes first. How many chars there are is
+    /// left for the parser to check.
+    fn extract_char_literal(&mut self) -> bool {
+        while !self.is_eof() && self.first() != '\n' {
+            let mark = self.mark();
+            match self.take_1() {
+                '\'' => return true,
+                '\\' => self.check_escape(mark),
+                _ => {}
+            }
+        }
+
+        false
     }
 
-    fn extract_double_quoted_string(&mut self) -> String {
-        let mut str = vec![
-            self.take_while(|c| c == '"'),
-            self.take_while(|c| c != '"'),
-            self.take_while(|c| c == '"'),
-        ];
+    /// Takes the escape sequence after a '\\' starting at `mark`, recording an error if it's bad
+    fn check_escape(&mut self, mark: Span) {
+        self.extract_escape();
+        let escape = self.slice_since(mark.start);
+        if unescape_char(&mut escape[1..].chars()).is_err() {
+            self.error(LexErrorKind::InvalidEscape, self.span_since(mark), escape);
+        }
+    }
 
-        str.concat()
+    /// Takes what follows a '\\' in a string, e.g. "n" or "u{1F600}"
+    fn extract_escape(&mut self) {
+        if self.take_1() == 'u' && self.first() == '{' {
+            self.take_1();
+            self.take_while(|c| c.is_ascii_hexdigit());
+            if self.first() == '}' {
+                self.take_1();
+            }
+        }
     }
 
-    fn advance_token(&mut self) -> Token {
-        let mut first_char = EOF_CHAR;
-        while let c = self.take_1() {
-            first_char = c;
-            if !c.is_ascii_whitespace() {
+    /// Whether the '"' just taken ends the string of an interpolation that is still waiting for
+    /// its '}'. That's the case when it can't start a string of its own inside the expression,
+    /// as no closing quote follows on the same line.
+    fn closes_unclosed_interpolation(&self) -> bool {
+        if self.interpolations.last().is_none_or(|i| i.depth != 0) {
+            return false;
+        }
+
+        let mut chars = self.chars.clone();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => return false,
+                '\n' => return true,
+                '\\' => {
+                    chars.next();
+                }
+                _ => {}
+            }
+        }
+
+        true
+    }
+
+    /// If the cursor is right after the 'r' of a raw string such as r#"..."#, returns how many
+    /// '#' the raw string uses
+    fn raw_string_hashes(&self) -> Option<usize> {
+        let rest = self.chars.as_str();
+        let hashes = rest.chars().take_while(|c| *c == '#').count();
+        rest[hashes..].starts_with('"').then_some(hashes)
+    }
+
+    /// Takes the rest of a raw string, starting from the '#'s after the 'r'. Returns false if the
+    /// string is never closed.
+    fn extract_raw_string(&mut self, hashes: usize) -> bool {
+        self.take_while(|c| c == '#');
+        self.take_1(); // Opening quote
+
+        while !self.is_eof() {
+            let rest = self.chars.as_str();
+            let closes = rest.starts_with('"')
+                && rest[1..].bytes().take_while(|b| *b == b'#').count() >= hashes;
+            if closes {
+                for _ in 0..hashes + 1 {
+                    self.take_1();
+                }
+
+                return true;
+            }
+
+            self.take_1();
+        }
+
+        false
+    }
+
+    /// Takes the rest of the line, leaving the line break (either "\n" or "\r\n") in place
+    fn extract_line_comment(&mut self) -> &'a str {
+        let start = self.offset();
+        while !self.is_eof() {
+            let c = self.first();
+            if c == '\n' || (c == '\r' && self.second() == '\n') {
                 break;
             }
 
-            self.pos_reset();
+            self.take_1();
+        }
+
+        self.slice_since(start)
+    }
+
+    /// Block comments nest, so "/* a /* b */ c */" is one comment. The opening "/*" is expected
+    /// to have been taken already. Returns false if the comment is never closed.
+    fn extract_block_comment(&mut self) -> bool {
+        let mut depth = 1;
+        while !self.is_eof() && depth > 0 {
+            match (self.first(), self.second()) {
+                ('/', '*') => depth += 1,
+                ('*', '/') => depth -= 1,
+                _ => {
+                    self.take_1();
+                    continue;
+                }
+            }
+
+            self.take_1();
+            self.take_1();
         }
 
-        let mut val = String::new();
+        depth == 0
+    }
+
+    fn advance_token(&mut self) -> Token<'a> {
+        // Checked up front, so that a '\0' in the source is an unknown char rather than the end
+        if self.is_eof() {
+            while let Some(interpolation) = self.interpolations.pop() {
+                self.error(LexErrorKind::UnterminatedString, interpolation.quote, "\"");
+            }
+
+            return Token::new(Eof, "", self.token_prefix_span(0));
+        }
+
+        let first_char = self.take_1();
         let token_kind = match first_char {
-            EOF_CHAR => Eof,
             '(' => OpenParen,
             ')' => CloseParen,
-            '{' => OpenBrace,
-            '}' => CloseBrace,
+            '{' => {
+                if let Some(interpolation) = self.interpolations.last_mut() {
+                    interpolation.depth += 1;
+                }
+
+                OpenBrace
+            }
+            '}' => match self.interpolations.last_mut() {
+                // Closes the interpolation, so what follows is string contents again
+                Some(Interpolation {
+                    quote, depth: 0, ..
+                }) => {
+                    let quote = *quote;
+                    match self.extract_double_quoted_string(true) {
+                        StringStop::Quote => {
+                            self.interpolations.pop();
+                            StringEnd
+                        }
+                        StringStop::OpenBrace(brace) => {
+                            if let Some(interpolation) = self.interpolations.last_mut() {
+                                interpolation.brace = brace;
+                            }
+                            StringMiddle
+                        }
+                        StringStop::Eof => {
+                            self.interpolations.pop();
+                            self.error(LexErrorKind::UnterminatedString, quote, "\"");
+                            UnterminatedString
+                        }
+                    }
+                }
+                Some(interpolation) => {
+                    interpolation.depth -= 1;
+                    CloseBrace
+                }
+                None => CloseBrace,
+            },
             '[' => OpenBracket,
             ']' => CloseBracket,
-            '+' => Plus,
-            '-' => Minus,
-            '*' => Star,
-            '/' => Slash,
+            '+' => match self.first() {
+                '=' => self.take_1_as(PlusEquals),
+                _ => Plus,
+            },
+            '-' => match self.first() {
+                '=' => self.take_1_as(MinusEquals),
+                '>' => self.take_1_as(Arrow),
+                _ => Minus,
+            },
+            '*' => match self.first() {
+                '=' => self.take_1_as(StarEquals),
+                '*' => self.take_1_as(StarStar),
+                _ => Star,
+            },
+            '/' => match self.first() {
+                '/' => {
+                    // The rest of the comment after its first '/'
+                    let comment = self.extract_line_comment();
+                    // "////" and longer are ordinary comments, same as in Rust
+                    if comment.starts_with("//") && !comment.starts_with("///") {
+                        DocComment
+                    } else {
+                        LineComment
+                    }
+                }
+                '*' => {
+                    self.take_1();
+                    if !self.extract_block_comment() {
+                        let span = self.token_prefix_span(2);
+                        self.error(LexErrorKind::UnterminatedBlockComment, span, "/*");
+                    }
+
+                    BlockComment
+                }
+                '=' => self.take_1_as(SlashEquals),
+                _ => Slash,
+            },
             '%' => Percent,
-            '=' => Equals,
+            '=' => match self.first() {
+                '=' => self.take_1_as(EqualsEquals),
+                '>' => self.take_1_as(FatArrow),
+                _ => Equals,
+            },
+            '!' => match self.first() {
+                '=' => self.take_1_as(BangEquals),
+                _ => Bang,
+            },
+            '<' => match self.first() {
+                '=' => self.take_1_as(LessEquals),
+                '<' => self.take_1_as(LessLess),
+                _ => Less,
+            },
+            '>' => match self.first() {
+                '=' => self.take_1_as(GreaterEquals),
+                '>' => self.take_1_as(GreaterGreater),
+                _ => Greater,
+            },
+            '&' => match self.first() {
+                '&' => self.take_1_as(AmpAmp),
+                _ => Amp,
+            },
+            '|' => match self.first() {
+                '|' => self.take_1_as(PipePipe),
+                _ => Pipe,
+            },
+            '^' => Caret,
+            ':' => match self.first() {
+                ':' => self.take_1_as(ColonColon),
+                _ => Colon,
+            },
+            ',' => Comma,
+            '.' => Dot,
             ';' => Semi,
+            '"' if self.closes_unclosed_interpolation() => {
+                // Likely a '{' that was meant literally, so end the string here rather than lex
+                // the rest of the file as its expression
+                let brace = self.interpolations.pop().unwrap().brace; // Checked in the match guard
+                let span = self.span_since(brace);
+                self.error(
+                    LexErrorKind::UnclosedInterpolation,
+                    span,
+                    self.slice_since(brace.start),
+                );
+                StringEnd
+            }
             '"' => {
-                let str = format!("{}{}", first_char, self.extract_double_quoted_string());
-                val = str;
-                StringLiteral
+                let quote = self.token_prefix_span(1);
+                match self.extract_double_quoted_string(true) {
+                    StringStop::Quote => StringLiteral,
+                    StringStop::OpenBrace(brace) => {
+                        self.interpolations.push(Interpolation {
+                            quote,
+                            brace,
+                            depth: 0,
+                        });
+                        StringStart
+                    }
+                    StringStop::Eof => {
+                        self.error(LexErrorKind::UnterminatedString, quote, "\"");
+                        UnterminatedString
+                    }
+                }
             }
 
-            c if c.is_ascii_whitespace() => Whitespace,
+            '\'' => {
+                if self.extract_char_literal() {
+                    CharLiteral
+                } else {
+                    let span = self.token_prefix_span(1);
+                    self.error(LexErrorKind::UnterminatedChar, span, "'");
+                    UnterminatedChar
+                }
+            }
 
-            c @ '0'..='9' => {
-                let num = format!("{}{}", first_char, self.extract_num());
-                val = num;
-                NumericLiteral
+            'b' if self.first() == '"' => {
+                self.take_1();
+                match self.extract_double_quoted_string(false) {
+                    StringStop::Quote => ByteStringLiteral,
+                    _ => {
+                        let span = self.token_prefix_span(2);
+                        self.error(LexErrorKind::UnterminatedString, span, "b\"");
+                        UnterminatedString
+                    }
+                }
             }
 
+            'r' if self.raw_string_hashes().is_some() => {
+                let hashes = self.raw_string_hashes().unwrap(); // Checked in the match guard
+                if self.extract_raw_string(hashes) {
+                    RawStringLiteral
+                } else {
+                    let opening = &self.src[self.token_start()..][..hashes + 2];
+                    let span = self.token_prefix_span(opening.len());
+                    self.error(LexErrorKind::UnterminatedString, span, opening);
+                    UnterminatedString
+                }
+            }
+
+            '\n' => Newline,
+            '\r' if self.first() == '\n' => self.take_1_as(Newline),
+            c if c.is_ascii_whitespace() => {
+                self.take_while(|c| c.is_ascii_whitespace() && c != '\n' && c != '\r');
+                Whitespace
+            }
+
+            '0'..='9' => self.extract_num(first_char),
+
             c if is_valid_id_start(c) => {
-                let ident = format!("{}{}", first_char, self.extract_ident());
-                let kind = match ident.as_str() {
-                    "let" => Let,
-                    "func" => Func,
-                    _ => Ident,
-                };
-
-                val = ident;
-                kind
+                self.extract_ident();
+                let ident = self.slice_since(self.token_start());
+                KEYWORDS
+                    .iter()
+                    .find(|(keyword, _)| *keyword == ident)
+                    .map_or(Ident, |(_, kind)| *kind)
             }
 
             _ => Unknown,
         };
 
+        let span = self.token_prefix_span(self.pos());
+        let val = &self.src[span.range()];
+        if token_kind == Unknown {
+            self.error(LexErrorKind::UnknownChar, span, val);
+        }
+
         let token = Token {
             kind: token_kind,
             val,
-            len: self.pos(),
+            span,
         };
 
         self.pos_reset();
@@ -143,417 +615,789 @@ impl<'a> Cursor<'a> {
     }
 }
 
-#[derive(Debug, PartialEq)]
-struct Val {
-    val: String,
+/// Location of a token in the source text.
+///
+/// `start..end` is a byte range, while `line` and `column` (both 1-based) point at the first
+/// character of the token. Columns are counted in characters, not bytes.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, line: usize, column: usize) -> Self {
+        Self {
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// Whether the two spans share at least one byte
+    pub fn overlaps(&self, other: Span) -> bool {
+        self.start < other.end && other.start < self.end
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
 }
 
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum LexErrorKind {
+    UnknownChar,
+    UnterminatedString,
+    UnterminatedChar,
+    UnterminatedBlockComment,
+    InvalidEscape,
+    UnclosedInterpolation,
+}
+
+/// A problem found while lexing. `span` and `text` cover the offending part of the source: the
+/// char, the escape sequence, or the opening quote or "/*" of something that is never closed.
 #[derive(Debug, PartialEq, Clone)]
-pub struct Token {
+pub struct LexError {
+    pub kind: LexErrorKind,
+    pub span: Span,
+    pub text: String,
+}
+
+impl Display for LexErrorKind {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let message = match self {
+            LexErrorKind::UnknownChar => "Unknown character",
+            LexErrorKind::UnterminatedString => {
+                "Unterminated string literal, missing closing quote"
+            }
+            LexErrorKind::UnterminatedChar => "Unterminated char literal, missing closing quote",
+            LexErrorKind::UnterminatedBlockComment => "Unterminated block comment, missing \"*/\"",
+            LexErrorKind::InvalidEscape => "Invalid escape sequence",
+            LexErrorKind::UnclosedInterpolation => {
+                "Unclosed interpolation, missing \"}\", write \"\\{\" for a literal brace"
+            }
+        };
+
+        message.fmt(f)
+    }
+}
+
+impl Display for LexError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}: {}: \"{}\"", self.span, self.kind, self.text)
+    }
+}
+
+/// `val` borrows the token's text from the source, so lexing doesn't allocate per token
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Token<'src> {
     pub kind: TokenKind,
-    pub val: String,
-    pub len: usize,
+    pub val: &'src str,
+    pub span: Span,
 }
 
-impl Token {
-    fn new(kind: TokenKind, val: String, len: usize) -> Self {
-        Self { kind, val, len }
+impl<'src> Token<'src> {
+    fn new(kind: TokenKind, val: &'src str, span: Span) -> Self {
+        Self { kind, val, span }
     }
 }
 
-impl TryFrom<&str> for Token {
+impl<'src> TryFrom<&'src str> for Token<'src> {
     type Error = String;
 
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
+    fn try_from(value: &'src str) -> Result<Self, Self::Error> {
         TokenIterator::from(value)
             .next()
             .ok_or(format!("Unable to tokenize: {value}"))
     }
 }
 
-impl Default for Token {
+impl Default for Token<'_> {
     fn default() -> Self {
         Self {
             kind: Eof,
-            val: String::new(),
-            len: 0,
+            val: "",
+            span: Span::default(),
+        }
+    }
+}
+
+impl Display for Token<'_> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        if self.kind.has_fixed_text() {
+            self.kind.fmt(f)
+        } else {
+            self.val.fmt(f) // Fixes weird alignment https://stackoverflow.com/a/77937993
         }
     }
 }
 
-impl Display for Token {
+/// The text of tokens that are always spelled the same, and a description of the others
+impl Display for TokenKind {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
-        let repr = match self.kind {
-            Let => "let".to_string(),
-            Func => "func".to_string(),
-            Ident => self.val.clone(),
-            StringLiteral => self.val.clone(),
-            NumericLiteral => self.val.clone(),
-            OpenParen => "(".to_string(),
-            CloseParen => ")".to_string(),
-            OpenBrace => "{".to_string(),
-            CloseBrace => "}".to_string(),
-            OpenBracket => "[".to_string(),
-            CloseBracket => "]".to_string(),
-            Semi => ";".to_string(),
-            Equals => "=".to_string(),
-            Plus => "+".to_string(),
-            Minus => "-".to_string(),
-            Star => "*".to_string(),
-            Slash => "/".to_string(),
-            Percent => "%".to_string(),
-            Whitespace => "WHITESPACE".to_string(),
-            Eof => "EOF".to_string(),
-            Unknown => "UNKNOWN".to_string(),
+        let repr = match self {
+            Ident => "identifier",
+            StringLiteral | RawStringLiteral => "string literal",
+            UnterminatedString => "unterminated string literal",
+            CharLiteral => "char literal",
+            UnterminatedChar => "unterminated char literal",
+            ByteStringLiteral => "byte string literal",
+            StringStart | StringMiddle | StringEnd => "interpolated string",
+            NumericLiteral => "integer literal",
+            FloatLiteral => "float literal",
+            OpenParen => "(",
+            CloseParen => ")",
+            OpenBrace => "{",
+            CloseBrace => "}",
+            OpenBracket => "[",
+            CloseBracket => "]",
+            Semi => ";",
+            Equals => "=",
+            Plus => "+",
+            Minus => "-",
+            Star => "*",
+            StarStar => "**",
+            Slash => "/",
+            // Spelled as in KEYWORDS, which stays the only place they are written out
+            Let | Func | True | False | If | Else | While | Loop | For | In | Return | Break
+            | Continue | Mut | Const | Struct | Enum | Match | Use => KEYWORDS
+                .iter()
+                .find(|(_, kind)| kind == self)
+                .map_or("keyword", |(keyword, _)| keyword),
+            Percent => "%",
+            PlusEquals => "+=",
+            MinusEquals => "-=",
+            StarEquals => "*=",
+            SlashEquals => "/=",
+            EqualsEquals => "==",
+            BangEquals => "!=",
+            Less => "<",
+            LessEquals => "<=",
+            Greater => ">",
+            GreaterEquals => ">=",
+            LessLess => "<<",
+            GreaterGreater => ">>",
+            Amp => "&",
+            Pipe => "|",
+            Caret => "^",
+            AmpAmp => "&&",
+            PipePipe => "||",
+            Bang => "!",
+            Arrow => "->",
+            FatArrow => "=>",
+            Comma => ",",
+            Dot => ".",
+            Colon => ":",
+            ColonColon => "::",
+            LineComment | BlockComment => "comment",
+            DocComment => "doc comment",
+            Whitespace => "WHITESPACE",
+            Newline => "NEWLINE",
+            Eof => "EOF",
+            Unknown => "UNKNOWN",
         };
 
-        repr.fmt(f) // Fixes weird alignment https://stackoverflow.com/a/77937993
+        repr.fmt(f)
     }
 }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
 pub enum TokenKind {
-    Ident,          // function & variable names
-    StringLiteral,  // String literals
-    NumericLiteral, // Numeric literals
-    OpenParen,      // (
-    CloseParen,     // )
-    OpenBrace,      // {
-    CloseBrace,     // }
-    OpenBracket,    // [
-    CloseBracket,   // ]
-    Semi,           // ;
-    Equals,         // =
-    Plus,           // +
-    Minus,          // -
-    Star,           // *
-    Slash,          // /
-    Let,            // let
-    Func,           // func
-    Percent,        // %
-    Whitespace,     //
-    Eof,            // \0
+    Ident,              // function & variable names
+    StringLiteral,      // String literals
+    RawStringLiteral,   // Raw string literals, r"..." or r#"..."#
+    UnterminatedString, // String literal that is missing its closing quote
+    CharLiteral,        // Char literals, 'a', '\n', '\u{1F600}'
+    UnterminatedChar,   // Char literal that is missing its closing quote
+    ByteStringLiteral,  // Byte string literals, b"bytes"
+    StringStart,        // "text { of an interpolated string
+    StringMiddle,       // } text { between two interpolations
+    StringEnd,          // } text" of an interpolated string
+    NumericLiteral,     // Integer literals, 10, 0xff, 0o17, 0b1010, 1_000
+    FloatLiteral,       // Float literals, 1.5, 2e10
+    OpenParen,          // (
+    CloseParen,         // )
+    OpenBrace,          // {
+    CloseBrace,         // }
+    OpenBracket,        // [
+    CloseBracket,       // ]
+    Semi,               // ;
+    Equals,             // =
+    Plus,               // +
+    Minus,              // -
+    Star,               // *
+    StarStar,           // **
+    Slash,              // /
+    Let,                // let
+    Func,               // func
+    True,               // true
+    False,              // false
+    If,                 // if
+    Else,               // else
+    While,              // while
+    Loop,               // loop
+    For,                // for
+    In,                 // in
+    Return,             // return
+    Break,              // break
+    Continue,           // continue
+    Mut,                // mut
+    Const,              // const
+    Struct,             // struct
+    Enum,               // enum
+    Match,              // match
+    Use,                // use
+    Percent,            // %
+    PlusEquals,         // +=
+    MinusEquals,        // -=
+    StarEquals,         // *=
+    SlashEquals,        // /=
+    EqualsEquals,       // ==
+    BangEquals,         // !=
+    Less,               // <
+    LessEquals,         // <=
+    Greater,            // >
+    GreaterEquals,      // >=
+    LessLess,           // <<
+    GreaterGreater,     // >>
+    Amp,                // &
+    Pipe,               // |
+    Caret,              // ^
+    AmpAmp,             // &&
+    PipePipe,           // ||
+    Bang,               // !
+    Arrow,              // ->
+    FatArrow,           // =>
+    Comma,              // ,
+    Dot,                // .
+    Colon,              // :
+    ColonColon,         // ::
+    LineComment,        // // comment
+    BlockComment,       // /* comment */
+    DocComment,         // /// comment
+    Whitespace,         // Spaces and tabs
+    Newline,            // \n or \r\n
+    Eof,                // \0
     Unknown,
 }
 
-pub(crate) struct TokenIterator<'a> {
-    inner: Box<dyn Iterator<Item = Token> + 'a>,
+/// Decodes the escape sequences in the contents of a string literal, i.e. the text between its
+/// quotes
+pub(crate) fn unescape(s: &str) -> Result<String, String> {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        unescaped.push(unescape_char(&mut chars)?);
+    }
+
+    Ok(unescaped)
 }
 
-impl<'a> From<&'a str> for TokenIterator<'a> {
-    fn from(s: &'a str) -> Self {
-        let mut cursor = Cursor::new(s);
-        let iter = std::iter::from_fn(move || {
-            let token = cursor.advance_token();
-            if token.kind == Eof {
-                None
-            } else {
-                Some(token)
-            }
-        });
+/// Decodes the escape sequence that `chars` is at, right after its '\\'
+fn unescape_char(chars: &mut Chars) -> Result<char, String> {
+    let c = match chars.next() {
+        Some('n') => '\n',
+        Some('t') => '\t',
+        Some('r') => '\r',
+        Some('0') => '\0',
+        Some('\\') => '\\',
+        Some('"') => '"',
+        Some('\'') => '\'',
+        Some('{') => '{',
+        Some('}') => '}',
+        Some('u') => unescape_unicode(chars)?,
+        Some(other) => return Err(format!("Unknown escape sequence: \"\\{other}\"")),
+        None => return Err("Expected escape sequence after \"\\\"".to_string()),
+    };
+
+    Ok(c)
+}
 
-        Self {
-            inner: Box::new(iter),
+/// Decodes the "{1F600}" part of a "\u{1F600}" escape
+fn unescape_unicode(chars: &mut Chars) -> Result<char, String> {
+    if chars.next() != Some('{') {
+        return Err("Expected \"{\" after \"\\u\"".to_string());
+    }
+
+    // Hex digits are ASCII, so counting them also counts their bytes
+    let digits = chars.as_str();
+    let mut len = 0;
+    loop {
+        match chars.next() {
+            Some('}') => break,
+            Some(c) if c.is_ascii_hexdigit() && len < 6 => len += 1,
+            _ => return Err("Unicode escape must be 1 to 6 hex digits in braces".to_string()),
         }
     }
+
+    let hex = &digits[..len];
+    u32::from_str_radix(hex, 16)
+        .ok()
+        .and_then(char::from_u32)
+        .ok_or(format!("Invalid unicode escape: \"\\u{{{hex}}}\""))
 }
 
-impl Iterator for TokenIterator<'_> {
-    type Item = Token;
+impl TokenKind {
+    pub fn is_keyword(&self) -> bool {
+        KEYWORDS.iter().any(|(_, kind)| kind == self)
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next()
+    /// Tokens whose text is always the same, like "(" or "let", as opposed to names and literals
+    pub fn has_fixed_text(&self) -> bool {
+        !matches!(
+            self,
+            Ident
+                | StringLiteral
+                | RawStringLiteral
+                | UnterminatedString
+                | CharLiteral
+                | UnterminatedChar
+                | ByteStringLiteral
+                | StringStart
+                | StringMiddle
+                | StringEnd
+                | NumericLiteral
+                | FloatLiteral
+                | LineComment
+                | BlockComment
+                | DocComment
+                | Unknown
+        )
+    }
+
+    /// Tokens that carry no meaning for the parser
+    pub fn is_trivia(&self) -> bool {
+        matches!(self, Whitespace | Newline | LineComment | BlockComment)
     }
 }
 
-pub(crate) struct RPNIterator<'a> {
-    other: VecDeque<Token>, // For all other symbols
-    rpn: VecDeque<Token>,
-    stack: Vec<Token>,
-    iter: Box<dyn Iterator<Item = Token> + 'a>,
+/// Iterates over the tokens of a source text. Lexing doesn't stop at malformed input: the
+/// offending text is still yielded as a token (e.g. `Unknown` or `UnterminatedString`), and the
+/// problem is recorded in `errors()`.
+pub struct TokenIterator<'a> {
+    cursor: Cursor<'a>,
+    lossless: bool,
 }
 
-impl<'a> RPNIterator<'a> {
-    // TODO: Couldn't implement FromIterator or From, so resorted to doing this
-    pub(crate) fn from_iter(mut iter: impl Iterator<Item = Token> + 'a) -> Self {
-        RPNIterator {
-            other: VecDeque::new(),
-            rpn: VecDeque::new(),
-            stack: vec![],
-            iter: Box::new(iter),
+impl<'a> TokenIterator<'a> {
+    /// Also yields trivia (whitespace, newlines and comments), so that concatenating the `val` of
+    /// every token reproduces `s` exactly
+    pub fn lossless(s: &'a str) -> Self {
+        Self {
+            cursor: Cursor::new(s),
+            lossless: true,
         }
     }
 
-    fn print_debug(&self) {
-        let space_count = 10;
+    /// Every lexical error found so far
+    pub fn errors(&self) -> &[LexError] {
+        &self.cursor.errors
+    }
+}
 
-        print!("other: ");
-        let mut used_up = 0;
-        for x in &self.other {
-            print!("{x} ");
-            used_up += 2;
+impl<'a> From<&'a str> for TokenIterator<'a> {
+    fn from(s: &'a str) -> Self {
+        Self {
+            cursor: Cursor::new(s),
+            lossless: false,
         }
+    }
+}
 
-        for _ in 0..=(space_count - used_up) {
-            print!(" ");
-        }
+impl<'a> Iterator for TokenIterator<'a> {
+    type Item = Token<'a>;
 
-        print!("rpn: ");
-        let mut used_up = 0;
-        for x in &self.rpn {
-            print!("{x} ");
-            used_up += 2;
-        }
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let token = self.cursor.advance_token();
+            if token.kind == Eof {
+                return None;
+            }
 
-        for _ in 0..=(space_count - used_up) {
-            print!(" ");
+            if self.lossless || !token.kind.is_trivia() {
+                trace::lex(&token);
+                return Some(token);
+            }
         }
+    }
+}
 
-        print!("stack: ");
-        let mut used_up = 0;
-        for x in &self.stack {
-            print!("{x} ");
-        }
+#[rustfmt::skip::macros(assert_eq)]
+#[cfg(test)]
+mod tests {
+    use crate::lexer::TokenKind::*;
+    use crate::lexer::{unescape, LexError, LexErrorKind, Span, Token, TokenIterator, KEYWORDS};
+    use proptest::prelude::*;
 
-        println!();
-    }
-
-    fn precedence(token: &Token) -> u8 {
-        match token.kind {
-            OpenParen => 3,
-            CloseParen => 3,
-            Star => 2,
-            Slash => 2,
-            Percent => 2,
-            Plus => 1,
-            Minus => 1,
-            _ => unreachable!(
-                "This TokenKind \"{:?}\" does not have operator precedence.",
-                token.kind
-            ),
-        }
+    fn lossless_text(s: &str) -> String {
+        TokenIterator::lossless(s).map(|t| t.val).collect()
     }
 
-    fn handle_other(&mut self, token: Token) {
-        self.other.push_back(token);
-    }
+    #[test]
+    fn tokenize_simple_func() {
+        let text = "func main() {}";
+        let mut token_iter = TokenIterator::from(text);
 
-    fn handle_parenthesis(&mut self, token: Token) {
-        // TODO LATER, NO-OP for now
+        assert_eq!(token_iter.next(), Some(Token::new(Func, "func", Span::new(0, 4, 1, 1))));
+        assert_eq!(token_iter.next(), Some(Token::new(Ident, "main", Span::new(5, 9, 1, 6))));
+        assert_eq!(token_iter.next(), Some(Token::new(OpenParen, "(", Span::new(9, 10, 1, 10))));
+        assert_eq!(token_iter.next(), Some(Token::new(CloseParen, ")", Span::new(10, 11, 1, 11))));
+        assert_eq!(token_iter.next(), Some(Token::new(OpenBrace, "{", Span::new(12, 13, 1, 13))));
+        assert_eq!(token_iter.next(), Some(Token::new(CloseBrace, "}", Span::new(13, 14, 1, 14))));
+        assert_eq!(token_iter.next(), None);
     }
 
-    fn handle_operand(&mut self, token: Token) {
-        self.rpn.push_back(token);
+    #[test]
+    fn tokenize_simple_program() {
+        let text =
+            "func main() {\nlet word1 = \"hello\";\nlet word2 = \" world!\";\nword1 + word2\n}";
+        let mut token_iter = TokenIterator::from(text);
+
+        // func main() {\n
+        assert_eq!(token_iter.next(), Some(Token::new(Func, "func", Span::new(0, 4, 1, 1))));
+        assert_eq!(token_iter.next(), Some(Token::new(Ident, "main", Span::new(5, 9, 1, 6))));
+        assert_eq!(token_iter.next(), Some(Token::new(OpenParen, "(", Span::new(9, 10, 1, 10))));
+        assert_eq!(token_iter.next(), Some(Token::new(CloseParen, ")", Span::new(10, 11, 1, 11))));
+        assert_eq!(token_iter.next(), Some(Token::new(OpenBrace, "{", Span::new(12, 13, 1, 13))));
+
+        // let word1 = "hello";\n
+        assert_eq!(token_iter.next(), Some(Token::new(Let, "let", Span::new(14, 17, 2, 1))));
+        assert_eq!(token_iter.next(), Some(Token::new(Ident, "word1", Span::new(18, 23, 2, 5))));
+        assert_eq!(token_iter.next(), Some(Token::new(Equals, "=", Span::new(24, 25, 2, 11))));
+        assert_eq!(token_iter.next(), Some(Token::new(StringLiteral, r#""hello""#, Span::new(26, 33, 2, 13))));
+        assert_eq!(token_iter.next(), Some(Token::new(Semi, ";", Span::new(33, 34, 2, 20))));
+
+        // let word2 = " world!";\n
+        assert_eq!(token_iter.next(), Some(Token::new(Let, "let", Span::new(35, 38, 3, 1))));
+        assert_eq!(token_iter.next(), Some(Token::new(Ident, "word2", Span::new(39, 44, 3, 5))));
+        assert_eq!(token_iter.next(), Some(Token::new(Equals, "=", Span::new(45, 46, 3, 11))));
+        assert_eq!(token_iter.next(), Some(Token::new(StringLiteral, r#"" world!""#, Span::new(47, 56, 3, 13))));
+        assert_eq!(token_iter.next(), Some(Token::new(Semi, ";", Span::new(56, 57, 3, 22))));
+
+        // word1 + word2\n
+        assert_eq!(token_iter.next(), Some(Token::new(Ident, "word1", Span::new(58, 63, 4, 1))));
+        assert_eq!(token_iter.next(), Some(Token::new(Plus, "+", Span::new(64, 65, 4, 7))));
+        assert_eq!(token_iter.next(), Some(Token::new(Ident, "word2", Span::new(66, 71, 4, 9))));
+
+        // }
+        assert_eq!(token_iter.next(), Some(Token::new(CloseBrace, "}", Span::new(72, 73, 5, 1))));
+        assert_eq!(token_iter.next(), None);
     }
 
-    fn handle_operator(&mut self, token: Token) {
-        let precedence_of_token = Self::precedence(&token);
+    #[test]
+    fn token_spans_across_crlf_and_multibyte_chars() {
+        let text = "let ä = \"ö\";\r\n  x\r\ny";
+        let mut token_iter = TokenIterator::from(text);
 
-        loop {
-            // TODO: Handle Parenthesis: https://www.youtube.com/watch?v=QxHRM0EQHiQ
-            let Some(last) = self.stack.last() else {
-                self.stack.push(token);
-                return;
-            };
-
-            let precedence_of_last = Self::precedence(last);
-            if precedence_of_last <= precedence_of_token {
-                self.stack.push(token);
-                return;
-            }
+        assert_eq!(token_iter.next(), Some(Token::new(Let, "let", Span::new(0, 3, 1, 1))));
+        assert_eq!(token_iter.next(), Some(Token::new(Ident, "ä", Span::new(4, 6, 1, 5))));
+        assert_eq!(token_iter.next(), Some(Token::new(Equals, "=", Span::new(7, 8, 1, 7))));
+        assert_eq!(token_iter.next(), Some(Token::new(StringLiteral, "\"ö\"", Span::new(9, 13, 1, 9))));
+        assert_eq!(token_iter.next(), Some(Token::new(Semi, ";", Span::new(13, 14, 1, 12))));
+        assert_eq!(token_iter.next(), Some(Token::new(Ident, "x", Span::new(18, 19, 2, 3))));
+        assert_eq!(token_iter.next(), Some(Token::new(Ident, "y", Span::new(21, 22, 3, 1))));
+        assert_eq!(token_iter.next(), None);
 
-            if precedence_of_last > precedence_of_token {
-                let last = self.stack.pop().unwrap();
-                self.rpn.push_back(last);
-            }
-        }
+        assert_eq!(&text[Span::new(9, 13, 1, 9).range()], "\"ö\"");
     }
-}
 
-impl Iterator for RPNIterator<'_> {
-    type Item = Token;
+    #[test]
+    fn tokenize_line_comments() {
+        let text = "10 // ten\r\n/ 2 //\n";
+        let mut token_iter = TokenIterator::from(text);
 
-    fn next(&mut self) -> Option<Token> {
-        while let Some(token) = self.iter.next() {
-            print!("{token:7}{:4}| ", " ");
-            match token.kind {
-                NumericLiteral | Ident => self.handle_operand(token),
-                OpenParen | CloseParen => self.handle_parenthesis(token),
-                Plus | Minus | Star | Slash | Percent => self.handle_operator(token),
-                _ => {
-                    self.handle_other(token);
-                    self.print_debug();
-                    break;
-                }
-            };
-            self.print_debug();
+        assert_eq!(token_iter.next(), Some(Token::new(NumericLiteral, "10", Span::new(0, 2, 1, 1))));
+        assert_eq!(token_iter.next(), Some(Token::new(Slash, "/", Span::new(11, 12, 2, 1))));
+        assert_eq!(token_iter.next(), Some(Token::new(NumericLiteral, "2", Span::new(13, 14, 2, 3))));
+        assert_eq!(token_iter.next(), None);
 
-            if !self.rpn.is_empty() {
-                return self.rpn.pop_front();
-            }
-        }
+        let mut token_iter = TokenIterator::lossless(text);
+        assert_eq!(token_iter.nth(2), Some(Token::new(LineComment, "// ten", Span::new(3, 9, 1, 4))));
+        assert_eq!(token_iter.next(), Some(Token::new(Newline, "\r\n", Span::new(9, 11, 1, 10))));
+    }
 
-        if !self.stack.is_empty() {
-            while let Some(top) = self.stack.pop() {
-                self.rpn.push_back(top)
-            }
-        }
+    #[test]
+    fn tokenize_nested_block_comments() {
+        let text = "a /* one /* two */ still one */ b /* unterminated /* */";
+        let mut token_iter = TokenIterator::lossless(text).filter(|t| t.kind != Whitespace);
+
+        assert_eq!(token_iter.next().map(|t| t.kind), Some(Ident));
+        assert_eq!(token_iter.next(), Some(Token::new(BlockComment, "/* one /* two */ still one */", Span::new(2, 31, 1, 3))));
+        assert_eq!(token_iter.next().map(|t| t.kind), Some(Ident));
+        assert_eq!(token_iter.next(), Some(Token::new(BlockComment, "/* unterminated /* */", Span::new(34, 55, 1, 35))));
+        assert_eq!(token_iter.next(), None);
 
-        if !self.rpn.is_empty() {
-            return self.rpn.pop_front();
-        }
+        let kinds = TokenIterator::from("1 /**/ * /*\n*/ 2")
+            .map(|t| t.kind)
+            .collect::<Vec<_>>();
+        assert_eq!(kinds, vec![NumericLiteral, Star, NumericLiteral]);
+    }
 
-        if !self.other.is_empty() {
-            return self.other.pop_front();
-        }
+    #[test]
+    fn tokenize_doc_comments() {
+        let text = "/// Entry point\n//// not a doc\nfunc main() {}";
+        let mut token_iter = TokenIterator::from(text);
 
-        None
+        assert_eq!(token_iter.next(), Some(Token::new(DocComment, "/// Entry point", Span::new(0, 15, 1, 1))));
+        assert_eq!(token_iter.next().map(|t| t.kind), Some(Func));
     }
-}
 
-#[rustfmt::skip::macros(assert_eq)]
-#[cfg(test)]
-mod tests {
-    use crate::lexer::TokenKind::*;
-    use crate::lexer::{RPNIterator, Token, TokenIterator, TokenKind};
+    #[test]
+    fn tokenize_multi_char_operators() {
+        let s = "a+=b-=c*=d/=e==f!=g<h<=i>j>=k&&l||!m->n=>o,p.q:r::s";
+        let kinds = TokenIterator::from(s)
+            .map(|t| t.kind)
+            .filter(|k| *k != Ident)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            kinds,
+            vec![
+                PlusEquals, MinusEquals, StarEquals, SlashEquals, EqualsEquals, BangEquals, Less,
+                LessEquals, Greater, GreaterEquals, AmpAmp, PipePipe, Bang, Arrow, FatArrow, Comma,
+                Dot, Colon, ColonColon,
+            ]
+        );
+    }
 
     #[test]
-    #[ignore]
-    fn invalid_expr_to_postfix_notation() {
-        let text =
-            "func main() {\nlet word1 = \"hello\";\nlet word2 = \" world!\";\nword1 + word2\n}";
-        let mut token_iter = TokenIterator::from(text);
-        let rpn_iter = RPNIterator::from_iter(token_iter);
-        let v = rpn_iter.collect::<Vec<Token>>();
-        println!("{v:?}");
+    fn tokenize_operators_with_maximal_munch() {
+        let kinds = |s: &str| TokenIterator::from(s).map(|t| t.kind).collect::<Vec<_>>();
+
+        assert_eq!(kinds("<=="), vec![LessEquals, Equals]);
+        assert_eq!(kinds("!=="), vec![BangEquals, Equals]);
+        assert_eq!(kinds("-->"), vec![Minus, Arrow]);
+        assert_eq!(kinds(":::"), vec![ColonColon, Colon]);
+        assert_eq!(kinds("==="), vec![EqualsEquals, Equals]);
+        assert_eq!(kinds("= ="), vec![Equals, Equals]);
+        assert_eq!(kinds("&&&"), vec![AmpAmp, Amp]);
+        assert_eq!(kinds("|||"), vec![PipePipe, Pipe]);
+        assert_eq!(kinds("<<= >>>"), vec![LessLess, Equals, GreaterGreater, Greater]);
+        assert_eq!(kinds("a^b"), vec![Ident, Caret, Ident]);
+        assert_eq!(kinds("***="), vec![StarStar, StarEquals]);
+        assert_eq!(kinds("a, b"), vec![Ident, Comma, Ident]);
+
+        let mut token_iter = TokenIterator::from("x >= 1");
+        token_iter.next();
+        assert_eq!(token_iter.next(), Some(Token::new(GreaterEquals, ">=", Span::new(2, 4, 1, 3))));
     }
 
     #[test]
-    fn complex_expr_to_postfix_notation_1() {
-        let expr = "let a = 10 + 5 * 2 - 3;";
+    fn tokenize_keywords() {
+        let s = "let func true false if else while loop for in return break continue mut const struct enum match use";
+        let tokens = TokenIterator::from(s).collect::<Vec<_>>();
+
+        assert_eq!(tokens.len(), KEYWORDS.len());
+        for (token, (keyword, kind)) in tokens.iter().zip(KEYWORDS) {
+            assert_eq!(token.kind, kind);
+            assert_eq!(token.val, keyword);
+            assert!(token.kind.is_keyword());
+            assert_eq!(token.to_string(), keyword);
+            assert_eq!(kind.to_string(), keyword);
+        }
+    }
 
-        let token_iter = TokenIterator::from(expr);
-        let mut rpn_iter = RPNIterator::from_iter(token_iter);
+    #[test]
+    fn tokenize_words_containing_keywords_as_idents() {
+        let s = "letter iffy index format truely _if";
+        let kinds = TokenIterator::from(s).map(|t| t.kind).collect::<Vec<_>>();
 
-        assert_eq!(rpn_iter.next().unwrap().kind, Let);
-        assert_eq!(rpn_iter.next().unwrap().kind, Ident);
-        assert_eq!(rpn_iter.next().unwrap().kind, Equals);
-        assert_eq!(rpn_iter.next(), Some(Token::new(NumericLiteral, "10".to_string(), 2)));
-        assert_eq!(rpn_iter.next(), Some(Token::new(NumericLiteral, "5".to_string(), 1)));
-        assert_eq!(rpn_iter.next(), Some(Token::new(NumericLiteral, "2".to_string(), 1)));
-        assert_eq!(rpn_iter.next(), Some(Token::new(Star, "".to_string(), 1)));
-        assert_eq!(rpn_iter.next(), Some(Token { kind: NumericLiteral, val: "3".to_string(), len: 1 }));
-        assert_eq!(rpn_iter.next(), Some(Token { kind: Minus, val: "".to_string(), len: 1 }));
-        assert_eq!(rpn_iter.next(), Some(Token { kind: Plus, val: "".to_string(), len: 1, }));
-        assert_eq!(rpn_iter.next(), Some(Token { kind: Semi, val: "".to_string(), len: 1, }));
+        assert_eq!(kinds, vec![Ident, Ident, Ident, Ident, Ident, Ident]);
+        assert!(!Ident.is_keyword());
     }
 
     #[test]
-    fn complex_expr_to_postfix_notation_2() {
-        let expr = "let a = hello + world * a - c;";
+    fn tokenize_snake_case_idents() {
+        let s = "my_var _private __dunder__ x1_y2 _";
+        let tokens = TokenIterator::from(s)
+            .map(|t| (t.kind, t.val))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            tokens,
+            vec![
+                (Ident, "my_var"),
+                (Ident, "_private"),
+                (Ident, "__dunder__"),
+                (Ident, "x1_y2"),
+                (Ident, "_"),
+            ]
+        );
+    }
 
-        let token_iter = TokenIterator::from(expr);
-        let mut rpn_iter = RPNIterator::from_iter(token_iter);
+    #[test]
+    fn tokenize_unicode_idents() {
+        let s = "größe 変数 Ωmega e\u{301}t\u{e9}";
+        let mut token_iter = TokenIterator::from(s);
 
-        assert_eq!(rpn_iter.next().unwrap().kind, Let);
-        assert_eq!(rpn_iter.next().unwrap().kind, Ident);
-        assert_eq!(rpn_iter.next().unwrap().kind, Equals);
-        assert_eq!(rpn_iter.next(), Some(Token::new(Ident, "hello".to_string(), 5)));
-        assert_eq!(rpn_iter.next(), Some(Token::new(Ident, "world".to_string(), 5)));
-        assert_eq!(rpn_iter.next(), Some(Token::new(Ident, "a".to_string(), 1)));
-        assert_eq!(rpn_iter.next(), Some(Token::new(Star, "".to_string(), 1)));
-        assert_eq!(rpn_iter.next(), Some(Token { kind: Ident, val: "c".to_string(), len: 1 }));
-        assert_eq!(rpn_iter.next(), Some(Token { kind: Minus, val: "".to_string(), len: 1 }));
-        assert_eq!(rpn_iter.next(), Some(Token { kind: Plus, val: "".to_string(), len: 1, }));
-        assert_eq!(rpn_iter.next(), Some(Token { kind: Semi, val: "".to_string(), len: 1, }));
+        assert_eq!(token_iter.next(), Some(Token::new(Ident, "größe", Span::new(0, 7, 1, 1))));
+        assert_eq!(token_iter.next(), Some(Token::new(Ident, "変数", Span::new(8, 14, 1, 7))));
+        assert_eq!(token_iter.next(), Some(Token::new(Ident, "Ωmega", Span::new(15, 21, 1, 10))));
+        // A combining mark continues an identifier
+        assert_eq!(token_iter.next(), Some(Token::new(Ident, "e\u{301}t\u{e9}", Span::new(22, 28, 1, 16))));
+        assert_eq!(token_iter.next(), None);
     }
 
     #[test]
-    fn simple_expr_to_postfix_notation_1() {
-        let expr = "10 + 5 * 2 - 3";
-        let token_iter = TokenIterator::from(expr);
-        let mut rpn_iter = RPNIterator::from_iter(token_iter);
-        println!("{:?}", rpn_iter.next());
-        println!("{:?}", rpn_iter.next());
-        println!("{:?}", rpn_iter.next());
-        println!("{:?}", rpn_iter.next());
-        println!("{:?}", rpn_iter.next());
-        println!("{:?}", rpn_iter.next());
-        println!("{:?}", rpn_iter.next());
+    fn tokenize_non_xid_chars() {
+        // A combining mark can't start an identifier, and neither can a digit or an emoji
+        let kinds = |s: &str| TokenIterator::from(s).map(|t| t.kind).collect::<Vec<_>>();
+
+        assert_eq!(kinds("\u{301}abc"), vec![Unknown, Ident]);
+        assert_eq!(kinds("1abc"), vec![NumericLiteral, Ident]);
+        assert_eq!(kinds("a😀b"), vec![Ident, Unknown, Ident]);
+        assert_eq!(kinds("a\u{200B}b"), vec![Ident, Unknown, Ident]);
     }
 
     #[test]
-    fn simple_expr_to_postfix_notation_2() {
-        let s = "10 + 20 * 5 - 15 / 3 * 6 + 4";
-        // 10 20 5 * 15 3 6 * / 4 + - +
-
-        let token_iter = TokenIterator::from(s);
-        let mut rpn_iter = RPNIterator::from_iter(token_iter);
-        assert_eq!(rpn_iter.next(), Some(Token::new(NumericLiteral,"10".to_string(), 2)));
-        assert_eq!(rpn_iter.next(), Some(Token::new(NumericLiteral,"20".to_string(), 2)));
-        assert_eq!(rpn_iter.next(), Some(Token::new(NumericLiteral,"5".to_string(), 1)));
-        assert_eq!(rpn_iter.next(), Some(Token::new(Star, "".to_string(), 1)));
-        assert_eq!(rpn_iter.next(), Some(Token::new(NumericLiteral,"15".to_string(), 2)));
-        assert_eq!(rpn_iter.next(), Some(Token::new(NumericLiteral,"3".to_string(), 1)));
-        assert_eq!(rpn_iter.next(), Some(Token::new(NumericLiteral,"6".to_string(), 1)));
-        assert_eq!(rpn_iter.next(), Some(Token::new(Star, "".to_string(), 1)));
-        assert_eq!(rpn_iter.next(), Some(Token::new(Slash, "".to_string(), 1)));
-        assert_eq!(rpn_iter.next(), Some(Token::new(NumericLiteral,"4".to_string(), 1)));
-        assert_eq!(rpn_iter.next(), Some(Token::new(Plus, "".to_string(), 1)));
-        assert_eq!(rpn_iter.next(), Some(Token::new(Minus, "".to_string(), 1)));
-        assert_eq!(rpn_iter.next(), Some(Token::new(Plus, "".to_string(), 1)));
-        assert_eq!(rpn_iter.next(), None);
+    fn lex_errors_for_unknown_chars() {
+        let mut token_iter = TokenIterator::from("let a = 1 @ 2;\n$b");
+        let tokens = token_iter.by_ref().collect::<Vec<_>>();
+
+        assert_eq!(tokens[4], Token::new(Unknown, "@", Span::new(10, 11, 1, 11)));
+        assert_eq!(tokens[5].kind, NumericLiteral); // Lexing carries on after the error
+        assert_eq!(
+            token_iter.errors(),
+            &[
+                LexError { kind: LexErrorKind::UnknownChar, span: Span::new(10, 11, 1, 11), text: "@".to_string() },
+                LexError { kind: LexErrorKind::UnknownChar, span: Span::new(15, 16, 2, 1), text: "$".to_string() },
+            ]
+        );
+        assert_eq!(token_iter.errors()[1].to_string(), r#"2:1: Unknown character: "$""#);
     }
 
     #[test]
-    fn tokenize_simple_func() {
-        let text = "func main() {}";
-        let mut token_iter = TokenIterator::from(text);
+    fn lex_error_for_nul_char() {
+        let mut token_iter = TokenIterator::from("a\0b");
 
-        assert_eq!(token_iter.next(), Some(Token { kind: Func, val: "func".to_string(), len: 4 }));
-        assert_eq!(token_iter.next(), Some(Token { kind: Ident, val: "main".to_string(), len: 4 }));
-        assert_eq!(token_iter.next(), Some(Token { kind: OpenParen, val: "".to_string(), len: 1 }));
-        assert_eq!(token_iter.next(), Some(Token { kind: CloseParen, val: "".to_string(), len: 1 }));
-        assert_eq!(token_iter.next(), Some(Token { kind: OpenBrace, val: "".to_string(), len: 1 }));
-        assert_eq!(token_iter.next(), Some(Token { kind: CloseBrace, val: "".to_string(), len: 1 }));
-        assert_eq!(token_iter.next(), None);
+        assert_eq!(token_iter.by_ref().map(|t| t.kind).collect::<Vec<_>>(), vec![Ident, Unknown, Ident]);
+        assert_eq!(token_iter.errors()[0].kind, LexErrorKind::UnknownChar);
     }
 
     #[test]
-    fn tokenize_simple_program() {
-        let text =
-            "func main() {\nlet word1 = \"hello\";\nlet word2 = \" world!\";\nword1 + word2\n}";
-        let mut token_iter = TokenIterator::from(text);
+    fn lex_errors_for_bad_escapes() {
+        let mut token_iter = TokenIterator::from(r#"x = "a\qb\u{110000}c\u{41}\n";"#);
+        let tokens = token_iter.by_ref().collect::<Vec<_>>();
+
+        assert_eq!(tokens[2].kind, StringLiteral);
+        assert_eq!(tokens[3].kind, Semi);
+        assert_eq!(
+            token_iter.errors(),
+            &[
+                LexError { kind: LexErrorKind::InvalidEscape, span: Span::new(6, 8, 1, 7), text: r"\q".to_string() },
+                LexError { kind: LexErrorKind::InvalidEscape, span: Span::new(9, 19, 1, 10), text: r"\u{110000}".to_string() },
+            ]
+        );
+    }
 
-        // func main() {\n
-        assert_eq!(token_iter.next(), Some(Token { kind: Func, val: "func".to_string(), len: 4 }));
-        assert_eq!(token_iter.next(), Some(Token { kind: Ident, val: "main".to_string(), len: 4 }));
-        assert_eq!(token_iter.next(), Some(Token { kind: OpenParen, val: "".to_string(), len: 1 }));
-        assert_eq!(token_iter.next(), Some(Token { kind: CloseParen, val: "".to_string(), len: 1 }));
-        assert_eq!(token_iter.next(), Some(Token { kind: OpenBrace, val: "".to_string(), len: 1 }));
+    #[test]
+    fn lex_errors_for_unterminated_strings_and_comments() {
+        let mut token_iter = TokenIterator::from("/* /* */\nr#\"a\"");
+        assert_eq!(token_iter.by_ref().count(), 0);
+        assert_eq!(
+            token_iter.errors(),
+            &[LexError { kind: LexErrorKind::UnterminatedBlockComment, span: Span::new(0, 2, 1, 1), text: "/*".to_string() }]
+        );
+
+        let mut token_iter = TokenIterator::from("a = 1;\nb = r##\"a\"#;\n");
+        assert_eq!(token_iter.by_ref().last().map(|t| t.kind), Some(UnterminatedString));
+        assert_eq!(
+            token_iter.errors(),
+            &[LexError { kind: LexErrorKind::UnterminatedString, span: Span::new(11, 15, 2, 5), text: "r##\"".to_string() }]
+        );
+    }
 
-        // let word1 = "hello";\n
-        assert_eq!(token_iter.next(), Some(Token { kind: Let, val: "let".to_string(), len: 3 }));
-        assert_eq!(token_iter.next(), Some(Token { kind: Ident, val: "word1".to_string(), len: 5 }));
-        assert_eq!(token_iter.next(), Some(Token { kind: Equals, val: "".to_string(), len: 1 }));
-        assert_eq!(token_iter.next(), Some(Token { kind: StringLiteral, val: r#""hello""#.to_string(), len: 7 }));
-        assert_eq!(token_iter.next(), Some(Token { kind: Semi, val: "".to_string(), len: 1 }));
+    #[test]
+    fn tokenize_lossless() {
+        let text = "let a = 1;\t// one\r\n\n  /* two */ b";
+        let tokens = TokenIterator::lossless(text).collect::<Vec<_>>();
+
+        assert_eq!(
+            tokens.iter().map(|t| t.kind).collect::<Vec<_>>(),
+            vec![Let, Whitespace, Ident, Whitespace, Equals, Whitespace, NumericLiteral, Semi, Whitespace, LineComment, Newline, Newline, Whitespace, BlockComment, Whitespace, Ident]
+        );
+        assert_eq!(tokens[11], Token::new(Newline, "\n", Span::new(19, 20, 2, 1)));
+        assert_eq!(tokens[12], Token::new(Whitespace, "  ", Span::new(20, 22, 3, 1)));
+        assert_eq!(lossless_text(text), text);
+    }
 
-        // let word2 = " world!";\n
-        assert_eq!(token_iter.next(), Some(Token { kind: Let, val: "let".to_string(), len: 3 }));
-        assert_eq!(token_iter.next(), Some(Token { kind: Ident, val: "word2".to_string(), len: 5}));
-        assert_eq!(token_iter.next(), Some(Token { kind: Equals, val: "".to_string(), len: 1 }));
-        assert_eq!(token_iter.next(), Some(Token { kind: StringLiteral, val: r#"" world!""#.to_string(), len: 9 }));
-        assert_eq!(token_iter.next(), Some(Token { kind: Semi, val: "".to_string(), len: 1 }));
+    #[test]
+    fn lossless_round_trip_with_errors() {
+        for text in [
+            "\"open \\q",
+            "a @ $ \0 b",
+            "/* /* */",
+            "r##\"x\"#",
+            "\r \r\r\n",
+            "1.e5 0x 9__",
+        ] {
+            assert_eq!(lossless_text(text), text);
+        }
+    }
 
-        // word1 + word2\n
-        assert_eq!(token_iter.next(), Some(Token { kind: Ident, val: "word1".to_string(), len: 5 }));
-        assert_eq!(token_iter.next(), Some(Token { kind: Plus, val: "".to_string(), len: 1 }));
-        assert_eq!(token_iter.next(), Some(Token { kind: Ident, val: "word2".to_string(), len: 5 }));
+    proptest! {
+        #[test]
+        fn lossless_round_trip_any_input(text in any::<String>()) {
+            prop_assert_eq!(lossless_text(&text), text);
+        }
 
-        // }
-        assert_eq!(token_iter.next(), Some(Token { kind: CloseBrace, val: "".to_string(), len: 1 }));
-        assert_eq!(token_iter.next(), None);
+        #[test]
+        fn lossless_round_trip_code_like_input(text in r#"([a-z0-9_ \t\r\n"'\\/*+\-=<>!&|(){};:,.#%]|r#"|0x){0,64}"#) {
+            let tokens = TokenIterator::lossless(&text).collect::<Vec<_>>();
+            prop_assert_eq!(tokens.iter().map(|t| t.val).collect::<String>(), text.clone());
+
+            // Spans cover the input without gaps or overlaps
+            let mut end = 0;
+            for token in &tokens {
+                prop_assert_eq!(token.span.start, end);
+                prop_assert_eq!(&text[token.span.range()], token.val);
+                end = token.span.end;
+            }
+            prop_assert_eq!(end, text.len());
+        }
+    }
+
+    #[test]
+    fn lex_all_errors_in_one_pass() {
+        let s = "let a = \"\\x\";\nlet b = 1 ? 2;\nlet c = \"open";
+        let mut token_iter = TokenIterator::from(s);
+        token_iter.by_ref().for_each(drop);
+
+        let errors = token_iter
+            .errors()
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![
+                r#"1:10: Invalid escape sequence: "\x""#,
+                r#"2:11: Unknown character: "?""#,
+                r#"3:9: Unterminated string literal, missing closing quote: """"#,
+            ]
+        );
     }
 
     #[test]
@@ -561,7 +1405,48 @@ mod tests {
         let s = "987654321 ";
         let mut token_iter = TokenIterator::from(s);
 
-        assert_eq!(token_iter.next(), Some(Token { kind: NumericLiteral, val: "987654321".to_string(), len: 9 }));
+        assert_eq!(token_iter.next(), Some(Token::new(NumericLiteral, "987654321", Span::new(0, 9, 1, 1))));
+        assert_eq!(token_iter.next(), None);
+    }
+
+    #[test]
+    fn tokenize_literal_nums_with_radix_and_separators() {
+        let s = "0xff 0o17 0b1010 1_000_000 0xFF_FF 0b12";
+        let tokens = TokenIterator::from(s)
+            .map(|t| (t.kind, t.val))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            tokens,
+            vec![
+                (NumericLiteral, "0xff"),
+                (NumericLiteral, "0o17"),
+                (NumericLiteral, "0b1010"),
+                (NumericLiteral, "1_000_000"),
+                (NumericLiteral, "0xFF_FF"),
+                (NumericLiteral, "0b12"),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenize_literal_floats() {
+        let s = "1.5 2e10 6.02E+23 1e-3 1_0.2_5 3.x 1e 7";
+        let mut token_iter = TokenIterator::from(s);
+
+        assert_eq!(token_iter.next(), Some(Token::new(FloatLiteral, "1.5", Span::new(0, 3, 1, 1))));
+        assert_eq!(token_iter.next(), Some(Token::new(FloatLiteral, "2e10", Span::new(4, 8, 1, 5))));
+        assert_eq!(token_iter.next(), Some(Token::new(FloatLiteral, "6.02E+23", Span::new(9, 17, 1, 10))));
+        assert_eq!(token_iter.next(), Some(Token::new(FloatLiteral, "1e-3", Span::new(18, 22, 1, 19))));
+        assert_eq!(token_iter.next(), Some(Token::new(FloatLiteral, "1_0.2_5", Span::new(23, 30, 1, 24))));
+
+        // Not floats: a "." must be followed by a digit, and an exponent must have digits
+        assert_eq!(token_iter.next(), Some(Token::new(NumericLiteral, "3", Span::new(31, 32, 1, 32))));
+        assert_eq!(token_iter.next().map(|t| t.kind), Some(Dot));
+        assert_eq!(token_iter.next().map(|t| t.kind), Some(Ident));
+        assert_eq!(token_iter.next(), Some(Token::new(NumericLiteral, "1", Span::new(35, 36, 1, 36))));
+        assert_eq!(token_iter.next(), Some(Token::new(Ident, "e", Span::new(36, 37, 1, 37))));
+        assert_eq!(token_iter.next(), Some(Token::new(NumericLiteral, "7", Span::new(38, 39, 1, 39))));
         assert_eq!(token_iter.next(), None);
     }
 
@@ -570,39 +1455,245 @@ mod tests {
         let s = r#"let text = "hello world";"#;
         let mut token_iter = TokenIterator::from(s);
 
-        assert_eq!(token_iter.next(), Some(Token { kind: Let, val: "let".to_string(), len: 3 }));
-        assert_eq!(token_iter.next(), Some(Token { kind: Ident, val: "text".to_string(), len: 4 }));
-        assert_eq!(token_iter.next(), Some(Token { kind: Equals, val: "".to_string(), len: 1 }));
-        assert_eq!(token_iter.next(), Some(Token { kind: StringLiteral, val: r#""hello world""#.to_string(), len: 13 }));
-        assert_eq!(token_iter.next(), Some(Token { kind: Semi, val: "".to_string(), len: 1 }));
+        assert_eq!(token_iter.next(), Some(Token::new(Let, "let", Span::new(0, 3, 1, 1))));
+        assert_eq!(token_iter.next(), Some(Token::new(Ident, "text", Span::new(4, 8, 1, 5))));
+        assert_eq!(token_iter.next(), Some(Token::new(Equals, "=", Span::new(9, 10, 1, 10))));
+        assert_eq!(token_iter.next(), Some(Token::new(StringLiteral, r#""hello world""#, Span::new(11, 24, 1, 12))));
+        assert_eq!(token_iter.next(), Some(Token::new(Semi, ";", Span::new(24, 25, 1, 25))));
 
         assert_eq!(token_iter.next(), None);
     }
 
+    #[test]
+    fn tokenize_string_with_escaped_quotes() {
+        let s = r#""say \"hi\" \\" x"#;
+        let mut token_iter = TokenIterator::from(s);
+
+        assert_eq!(token_iter.next(), Some(Token::new(StringLiteral, r#""say \"hi\" \\""#, Span::new(0, 15, 1, 1))));
+        assert_eq!(token_iter.next().map(|t| t.kind), Some(Ident));
+        assert_eq!(token_iter.next(), None);
+    }
+
+    #[test]
+    fn tokenize_raw_strings() {
+        let s = "r\"C:\\path\" r#\"two\nlines \"quoted\"\"# r##\"a \"# b\"## raw";
+        let mut token_iter = TokenIterator::from(s);
+
+        assert_eq!(token_iter.next(), Some(Token::new(RawStringLiteral, "r\"C:\\path\"", Span::new(0, 10, 1, 1))));
+        assert_eq!(token_iter.next(), Some(Token::new(RawStringLiteral, "r#\"two\nlines \"quoted\"\"#", Span::new(11, 34, 1, 12))));
+        assert_eq!(token_iter.next(), Some(Token::new(RawStringLiteral, "r##\"a \"# b\"##", Span::new(35, 48, 2, 18))));
+        assert_eq!(token_iter.next(), Some(Token::new(Ident, "raw", Span::new(49, 52, 2, 32))));
+        assert_eq!(token_iter.next(), None);
+    }
+
+    #[test]
+    fn tokenize_char_literals() {
+        let s = r"'a' '\n' '\u{1F600}' '\'' '😀' '' 'ab'";
+        let tokens = TokenIterator::from(s)
+            .map(|t| (t.kind, t.val))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            tokens,
+            vec![
+                (CharLiteral, "'a'"),
+                (CharLiteral, r"'\n'"),
+                (CharLiteral, r"'\u{1F600}'"),
+                (CharLiteral, r"'\''"),
+                (CharLiteral, "'😀'"),
+                (CharLiteral, "''"),
+                (CharLiteral, "'ab'"),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenize_unterminated_char_literals() {
+        let mut token_iter = TokenIterator::from("x = 'a;\ny = '\\q'");
+
+        assert_eq!(token_iter.nth(2), Some(Token::new(UnterminatedChar, "'a;", Span::new(4, 7, 1, 5))));
+        assert_eq!(token_iter.next().map(|t| t.kind), Some(Ident)); // Stops at the end of the line
+        assert_eq!(token_iter.nth(1).map(|t| t.kind), Some(CharLiteral));
+        assert_eq!(
+            token_iter.errors(),
+            &[
+                LexError { kind: LexErrorKind::UnterminatedChar, span: Span::new(4, 5, 1, 5), text: "'".to_string() },
+                LexError { kind: LexErrorKind::InvalidEscape, span: Span::new(13, 15, 2, 6), text: r"\q".to_string() },
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenize_byte_strings() {
+        let s = r#"b"bytes\n" b"{not interpolated}" b "x" bx"#;
+        let tokens = TokenIterator::from(s)
+            .map(|t| (t.kind, t.val))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            tokens,
+            vec![
+                (ByteStringLiteral, r#"b"bytes\n""#),
+                (ByteStringLiteral, r#"b"{not interpolated}""#),
+                (Ident, "b"),
+                (StringLiteral, r#""x""#),
+                (Ident, "bx"),
+            ]
+        );
+
+        let mut token_iter = TokenIterator::from("a b\"abc");
+        assert_eq!(token_iter.nth(1), Some(Token::new(UnterminatedString, "b\"abc", Span::new(2, 7, 1, 3))));
+        assert_eq!(
+            token_iter.errors(),
+            &[LexError { kind: LexErrorKind::UnterminatedString, span: Span::new(2, 4, 1, 3), text: "b\"".to_string() }]
+        );
+    }
+
+    #[test]
+    fn tokenize_unterminated_strings() {
+        let s = "let a = \"abc\\\";\nlet b = 1;";
+        let mut token_iter = TokenIterator::from(s);
+
+        assert_eq!(token_iter.next().map(|t| t.kind), Some(Let));
+        assert_eq!(token_iter.next().map(|t| t.kind), Some(Ident));
+        assert_eq!(token_iter.next().map(|t| t.kind), Some(Equals));
+        assert_eq!(token_iter.next(), Some(Token::new(UnterminatedString, "\"abc\\\";\nlet b = 1;", Span::new(8, 26, 1, 9))));
+        assert_eq!(token_iter.next(), None);
+
+        let mut token_iter = TokenIterator::from("r#\"abc\"");
+        assert_eq!(token_iter.next().map(|t| t.kind), Some(UnterminatedString));
+        assert_eq!(token_iter.next(), None);
+    }
+
+    #[test]
+    fn tokenize_interpolated_strings() {
+        let s = r#""Hello {name}, you are {age + 1}!""#;
+        let tokens = TokenIterator::from(s)
+            .map(|t| (t.kind, t.val))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            tokens,
+            vec![
+                (StringStart, "\"Hello {"),
+                (Ident, "name"),
+                (StringMiddle, "}, you are {"),
+                (Ident, "age"),
+                (Plus, "+"),
+                (NumericLiteral, "1"),
+                (StringEnd, "}!\""),
+            ]
+        );
+        assert_eq!(TokenIterator::from(s).nth(2).unwrap().span, Span::new(12, 24, 1, 13));
+    }
+
+    #[test]
+    fn tokenize_nested_interpolations() {
+        let s = r#""a {{ b }} {"x {y}"} \{c\}" }"#;
+        let mut token_iter = TokenIterator::from(s);
+        let tokens = token_iter
+            .by_ref()
+            .map(|t| (t.kind, t.val))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            tokens,
+            vec![
+                (StringStart, "\"a {"),
+                (OpenBrace, "{"),
+                (Ident, "b"),
+                (CloseBrace, "}"),
+                (StringMiddle, "} {"),
+                (StringStart, "\"x {"),
+                (Ident, "y"),
+                (StringEnd, "}\""),
+                (StringEnd, r#"} \{c\}""#),
+                (CloseBrace, "}"),
+            ]
+        );
+        assert_eq!(token_iter.errors(), &[]);
+    }
+
+    #[test]
+    fn tokenize_unterminated_interpolations() {
+        let mut token_iter = TokenIterator::from("x = \"a {b} c");
+        assert_eq!(token_iter.by_ref().last(), Some(Token::new(UnterminatedString, "} c", Span::new(9, 12, 1, 10))));
+        assert_eq!(
+            token_iter.errors(),
+            &[LexError { kind: LexErrorKind::UnterminatedString, span: Span::new(4, 5, 1, 5), text: "\"".to_string() }]
+        );
+
+        let mut token_iter = TokenIterator::from("\"a {b");
+        assert_eq!(token_iter.by_ref().map(|t| t.kind).collect::<Vec<_>>(), vec![StringStart, Ident]);
+        assert_eq!(token_iter.errors()[0].span, Span::new(0, 1, 1, 1));
+    }
+
+    #[test]
+    fn tokenize_unclosed_interpolations() {
+        let mut token_iter = TokenIterator::from("let a = \"{\";\nlet b = \"x {1 + \";");
+        let tokens = token_iter
+            .by_ref()
+            .map(|t| (t.kind, t.val))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            vec![
+                (Let, "let"), (Ident, "a"), (Equals, "="), (StringStart, "\"{"), (StringEnd, "\""), (Semi, ";"),
+                (Let, "let"), (Ident, "b"), (Equals, "="), (StringStart, "\"x {"), (NumericLiteral, "1"), (Plus, "+"), (StringEnd, "\""), (Semi, ";"),
+            ]
+        );
+        assert_eq!(
+            token_iter.errors(),
+            &[
+                LexError { kind: LexErrorKind::UnclosedInterpolation, span: Span::new(9, 11, 1, 10), text: "{\"".to_string() },
+                LexError { kind: LexErrorKind::UnclosedInterpolation, span: Span::new(24, 30, 2, 12), text: "{1 + \"".to_string() },
+            ]
+        );
+
+        // A string inside the interpolation is still lexed as one
+        let kinds = TokenIterator::from(r#""{"}"}""#)
+            .map(|t| t.kind)
+            .collect::<Vec<_>>();
+        assert_eq!(kinds, vec![StringStart, StringLiteral, StringEnd]);
+    }
+
+    #[test]
+    fn unescape_string_contents() {
+        assert_eq!(unescape(r#"a\tb\nc\\d\"e"#), Ok("a\tb\nc\\d\"e".to_string()));
+        assert_eq!(unescape(r"\u{1F600}\u{e9}"), Ok("😀é".to_string()));
+        assert_eq!(unescape(r"\{not interpolated\}"), Ok("{not interpolated}".to_string()));
+        assert_eq!(unescape(r"\q"), Err(r#"Unknown escape sequence: "\q""#.to_string()));
+        assert_eq!(unescape(r"\u{D800}"), Err(r#"Invalid unicode escape: "\u{D800}""#.to_string()));
+        assert!(unescape(r"\u{}").is_err());
+        assert!(unescape(r"\u{1234567}").is_err());
+        assert!(unescape(r"\u1234").is_err());
+        assert!(unescape("\\").is_err());
+    }
+
     #[test]
     fn tokenize_expression() {
         let s = "10 + 20 * 5 - 15 / 3 * 6 + 4";
         let mut token_iter = TokenIterator::from(s);
 
-        assert_eq!(token_iter.next(), Some(Token::new(NumericLiteral, "10".to_string(), 2)));
-        assert_eq!(token_iter.next(), Some(Token::new(Plus, "".to_string(), 1)));
+        assert_eq!(token_iter.next(), Some(Token::new(NumericLiteral, "10", Span::new(0, 2, 1, 1))));
+        assert_eq!(token_iter.next(), Some(Token::new(Plus, "+", Span::new(3, 4, 1, 4))));
 
-        assert_eq!(token_iter.next(), Some(Token::new(NumericLiteral, "20".to_string(), 2)));
-        assert_eq!(token_iter.next(), Some(Token::new(Star, "".to_string(), 1)));
+        assert_eq!(token_iter.next(), Some(Token::new(NumericLiteral, "20", Span::new(5, 7, 1, 6))));
+        assert_eq!(token_iter.next(), Some(Token::new(Star, "*", Span::new(8, 9, 1, 9))));
 
-        assert_eq!(token_iter.next(), Some(Token::new(NumericLiteral, "5".to_string(), 1)));
-        assert_eq!(token_iter.next(), Some(Token::new(Minus, "".to_string(), 1)));
+        assert_eq!(token_iter.next(), Some(Token::new(NumericLiteral, "5", Span::new(10, 11, 1, 11))));
+        assert_eq!(token_iter.next(), Some(Token::new(Minus, "-", Span::new(12, 13, 1, 13))));
 
-        assert_eq!(token_iter.next(), Some(Token::new(NumericLiteral, "15".to_string(), 2)));
-        assert_eq!(token_iter.next(), Some(Token::new(Slash, "".to_string(), 1)));
+        assert_eq!(token_iter.next(), Some(Token::new(NumericLiteral, "15", Span::new(14, 16, 1, 15))));
+        assert_eq!(token_iter.next(), Some(Token::new(Slash, "/", Span::new(17, 18, 1, 18))));
 
-        assert_eq!(token_iter.next(), Some(Token::new(NumericLiteral, "3".to_string(), 1)));
-        assert_eq!(token_iter.next(), Some(Token::new(Star, "".to_string(), 1)));
+        assert_eq!(token_iter.next(), Some(Token::new(NumericLiteral, "3", Span::new(19, 20, 1, 20))));
+        assert_eq!(token_iter.next(), Some(Token::new(Star, "*", Span::new(21, 22, 1, 22))));
 
-        assert_eq!(token_iter.next(), Some(Token::new(NumericLiteral, "6".to_string(), 1)));
-        assert_eq!(token_iter.next(), Some(Token::new(Plus, "".to_string(), 1)));
+        assert_eq!(token_iter.next(), Some(Token::new(NumericLiteral, "6", Span::new(23, 24, 1, 24))));
+        assert_eq!(token_iter.next(), Some(Token::new(Plus, "+", Span::new(25, 26, 1, 26))));
 
-        assert_eq!(token_iter.next(), Some(Token::new(NumericLiteral, "4".to_string(), 1)));
+        assert_eq!(token_iter.next(), Some(Token::new(NumericLiteral, "4", Span::new(27, 28, 1, 28))));
 
         assert_eq!(token_iter.next(), None);
     }
@@ -0,0 +1,249 @@
+use std::ops::Range;
+
+use crate::lexer::{Cursor, Span, Token, TokenKind};
+
+/// Replaces `range` (byte offsets into the old text) with `text`
+#[derive(Debug, PartialEq, Clone)]
+pub struct TextEdit<'e> {
+    pub range: Range<usize>,
+    pub text: &'e str,
+}
+
+impl<'e> TextEdit<'e> {
+    pub fn new(range: Range<usize>, text: &'e str) -> Self {
+        Self { range, text }
+    }
+
+    /// Applies the edit to `old`, giving the text to re-lex
+    pub fn apply(&self, old: &str) -> String {
+        format!(
+            "{}{}{}",
+            &old[..self.range.start],
+            self.text,
+            &old[self.range.end..]
+        )
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Relexed<'src> {
+    /// Lossless tokens of the new text, the same as `TokenIterator::lossless()` would give
+    pub tokens: Vec<Token<'src>>,
+    /// Indices into `tokens` that were lexed again, everything else was carried over
+    pub changed: Range<usize>,
+}
+
+/// Updates `old_tokens`, the lossless tokens of some text, after `edit` turned that text into
+/// `new_src`. Only the tokens around the edit are lexed again: once a freshly lexed token past
+/// the edit lines up with an old one, the rest of the old tokens are reused with shifted spans.
+/// An edit that opens or closes a string or block comment keeps going until that happens, which
+/// may be the end of the text.
+pub fn relex<'src>(old_tokens: &[Token], edit: &TextEdit, new_src: &'src str) -> Relexed<'src> {
+    let mut first = old_tokens
+        .iter()
+        .position(|token| token.span.end >= edit.range.start)
+        .unwrap_or(old_tokens.len());
+    let mut depth = old_tokens[..first]
+        .iter()
+        .map(|token| nesting(token.kind))
+        .sum::<isize>();
+
+    // A token's kind can depend on a few chars after it (e.g. "1." followed by a digit, or r###
+    // followed by '"'), but never on anything past a newline, so start at the beginning of the
+    // line the edit is on. The lexer starts outside of any interpolated string, so back up past
+    // those too.
+    while first > 0 && (depth != 0 || old_tokens.get(first).is_none_or(|t| t.span.column != 1)) {
+        first -= 1;
+        depth -= nesting(old_tokens[first].kind);
+    }
+
+    let mut tokens = old_tokens[..first]
+        .iter()
+        .map(|token| Token::new(token.kind, &new_src[token.span.range()], token.span))
+        .collect::<Vec<_>>();
+
+    let restart = old_tokens
+        .get(first)
+        .map_or(Span::new(0, 0, 1, 1), |t| t.span);
+    let mut cursor = Cursor::at(new_src, restart.start, restart.line, restart.column);
+
+    // Old tokens past the edit sit at `old start + new_edit_end - edit.range.end` in the new text
+    let new_edit_end = edit.range.start + edit.text.len();
+    let mut old = first;
+    loop {
+        let token = cursor.advance_token();
+        if token.kind == TokenKind::Eof {
+            break;
+        }
+
+        tokens.push(token);
+        if token.span.start < new_edit_end {
+            continue;
+        }
+
+        let old_start = token.span.start - new_edit_end + edit.range.end;
+        while old_tokens
+            .get(old)
+            .is_some_and(|t| t.span.start < old_start)
+        {
+            depth += nesting(old_tokens[old].kind);
+            old += 1;
+        }
+
+        let Some(matching) = old_tokens.get(old).filter(|t| {
+            t.span.start == old_start && t.kind == token.kind && t.span.len() == token.span.len()
+        }) else {
+            continue;
+        };
+
+        // Both lexers have to be outside of interpolated strings for the rest to lex the same
+        if depth + nesting(matching.kind) != 0 || !cursor.interpolations.is_empty() {
+            continue;
+        }
+
+        let changed = first..tokens.len();
+        tokens.extend(old_tokens[old + 1..].iter().map(|t| {
+            let span = shift(t.span, matching.span, token.span);
+            Token::new(t.kind, &new_src[span.range()], span)
+        }));
+
+        return Relexed { tokens, changed };
+    }
+
+    let changed = first..tokens.len();
+    Relexed { tokens, changed }
+}
+
+/// How many interpolated strings are left open after `kind`
+fn nesting(kind: TokenKind) -> isize {
+    match kind {
+        TokenKind::StringStart => 1,
+        TokenKind::StringEnd => -1,
+        _ => 0,
+    }
+}
+
+/// Moves `span` along with `from`, an earlier span on or before its line, which is now at `to`
+fn shift(span: Span, from: Span, to: Span) -> Span {
+    let column = if span.line == from.line {
+        span.column - from.column + to.column
+    } else {
+        span.column
+    };
+
+    Span {
+        start: span.start - from.start + to.start,
+        end: span.end - from.start + to.start,
+        line: span.line - from.line + to.line,
+        column,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer::incremental::{relex, Relexed, TextEdit};
+    use crate::lexer::TokenIterator;
+    use crate::lexer::TokenKind::*;
+    use proptest::prelude::*;
+
+    /// Checks `relex()` against lexing the edited text from scratch
+    fn relex_and_compare(old: &str, edit: TextEdit) -> (String, Relexed<'static>) {
+        let old_tokens = TokenIterator::lossless(old).collect::<Vec<_>>();
+        let new_src: &'static str = edit.apply(old).leak();
+        let relexed = relex(&old_tokens, &edit, new_src);
+
+        assert_eq!(
+            relexed.tokens,
+            TokenIterator::lossless(new_src).collect::<Vec<_>>()
+        );
+        (new_src.to_string(), relexed)
+    }
+
+    #[test]
+    fn relex_only_the_edited_line() {
+        let old = "let a = 1;\nlet b = 2;\nlet c = 3;\n";
+        let (new, relexed) = relex_and_compare(old, TextEdit::new(15..16, "bee"));
+
+        assert_eq!(new, "let a = 1;\nlet bee = 2;\nlet c = 3;\n");
+        assert_eq!(relexed.changed, 9..13); // From "let" up to the space after "bee"
+        assert_eq!(relexed.tokens[11].val, "bee");
+        assert_eq!(relexed.tokens.last().unwrap().span.line, 3);
+    }
+
+    #[test]
+    fn relex_shifts_spans_after_the_edit() {
+        let old = "a + b; c\nd";
+        let (_, relexed) = relex_and_compare(old, TextEdit::new(0..1, "x\ny"));
+
+        let d = relexed.tokens.last().unwrap();
+        assert_eq!((d.val, d.span.line, d.span.column), ("d", 3, 1));
+        let c = relexed.tokens[relexed.tokens.len() - 3];
+        assert_eq!(
+            (c.val, c.span.start, c.span.line, c.span.column),
+            ("c", 9, 2, 8)
+        );
+    }
+
+    #[test]
+    fn relex_opening_and_closing_a_string() {
+        let old = "let a = 1;\nlet b = \"x\";\nlet c = 3;";
+
+        let (_, opened) = relex_and_compare(old, TextEdit::new(8..9, "\""));
+        assert_eq!(opened.tokens[6].kind, StringLiteral);
+        assert_eq!(opened.tokens[6].val, "\";\nlet b = \"");
+
+        let (_, closed) = relex_and_compare(old, TextEdit::new(19..20, ""));
+        assert!(closed.tokens.iter().any(|t| t.kind == UnterminatedString));
+        assert_eq!(closed.changed.end, closed.tokens.len());
+    }
+
+    #[test]
+    fn relex_opening_and_closing_a_block_comment() {
+        let old = "a /* b */ c\nd\ne";
+
+        let (_, opened) = relex_and_compare(old, TextEdit::new(12..12, "/*"));
+        assert_eq!(opened.tokens.last().unwrap().val, "/*d\ne");
+
+        let (_, closed) = relex_and_compare(old, TextEdit::new(7..9, ""));
+        assert_eq!(closed.tokens[2].val, "/* b  c\nd\ne");
+    }
+
+    #[test]
+    fn relex_edits_that_merge_and_split_tokens() {
+        relex_and_compare("a = b", TextEdit::new(3..4, ""));
+        relex_and_compare("a == b", TextEdit::new(3..3, " "));
+        relex_and_compare("r#x", TextEdit::new(2..3, "\"a\"#"));
+        relex_and_compare("1. 5", TextEdit::new(2..3, ""));
+        relex_and_compare("x\r\n", TextEdit::new(2..3, ""));
+        relex_and_compare("", TextEdit::new(0..0, "let a;"));
+        relex_and_compare("let a;", TextEdit::new(0..6, ""));
+    }
+
+    #[test]
+    fn relex_inside_interpolated_strings() {
+        let old = "a = \"x {\n  b\n} y\";\nc";
+
+        let (_, edited) = relex_and_compare(old, TextEdit::new(12..13, "bee"));
+        assert_eq!(edited.changed.start, 0); // Backs up to the line the string starts on
+
+        let (_, closed) = relex_and_compare(old, TextEdit::new(14..15, ""));
+        assert_eq!(closed.tokens.last().unwrap().kind, Ident);
+
+        relex_and_compare(old, TextEdit::new(8..9, "\\{"));
+        relex_and_compare("\"{a}\" b", TextEdit::new(4..4, "{"));
+    }
+
+    proptest! {
+        #[test]
+        fn relex_matches_a_full_lex(
+            old in r#"([a-z0-9 \n"'/*#.={}]|r#"|\*/){0,40}"#,
+            start in 0..=40usize,
+            len in 0..=8usize,
+            text in r#"([a-z0-9 \n"'/*#.={}]|r#"|\*/){0,6}"#,
+        ) {
+            let start = start.min(old.len());
+            let end = (start + len).min(old.len());
+            relex_and_compare(&old, TextEdit::new(start..end, &text));
+        }
+    }
+}
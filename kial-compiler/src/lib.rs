@@ -1,4 +1,43 @@
 pub(crate) mod ast;
+mod env;
 pub mod lexer;
 pub(crate) mod pear;
 mod tokenstream;
+pub mod trace;
+mod val;
+
+pub use ast::module::Module;
+pub use pear::error::{Found, ParseError, ParseErrorKind};
+pub use val::Val;
+
+use pear::Pear;
+
+/// Parses a whole source file into its top-level items, or returns every syntax error in it
+pub fn parse_module(src: &str) -> Result<Module, Vec<ParseError>> {
+    let mut pear = Pear::from(src);
+    let module = Module::try_from(&mut pear);
+
+    let mut diagnostics = pear
+        .lex_errors()
+        .iter()
+        .cloned()
+        .map(ParseError::from)
+        .collect::<Vec<_>>();
+    let mut parse_errors = pear.take_diagnostics();
+    let module = module.map_err(|error| parse_errors.push(error)).ok();
+
+    // A token the lexer flagged usually fails to parse as well, which doesn't tell the user more
+    parse_errors.retain(|error| {
+        !diagnostics
+            .iter()
+            .any(|lexical| lexical.span.overlaps(error.span))
+    });
+    diagnostics.extend(parse_errors);
+
+    // The lexer and the parser report separately, so put their errors back in source order
+    diagnostics.sort_by_key(|error| error.span.start);
+    match module {
+        Some(module) if diagnostics.is_empty() => Ok(module),
+        _ => Err(diagnostics),
+    }
+}
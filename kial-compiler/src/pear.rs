@@ -1,66 +1,123 @@
-use crate::lexer::{Token, TokenKind};
+use crate::lexer::{LexError, Span, Token, TokenKind};
 use crate::tokenstream::TokenStream;
+use crate::trace;
+use error::{ParseError, ParseErrorKind};
+
+pub mod error;
 
 pub(crate) struct Pear<'a> {
     ts: TokenStream<'a>,
+    /// The span of the last consumed token, where errors at the end of the input point
+    previous: Span,
+    /// Errors that were recovered from, in the order they were found
+    diagnostics: Vec<ParseError>,
 }
 
-impl Pear<'_> {
-    fn take_while(&mut self, pred: fn(Token) -> bool) {
-        while let Some(next) = self.peek_next() {
-            if !pred(next) {
-                return;
-            }
+impl<'a> Pear<'a> {
+    pub(crate) fn extract_identifier(&mut self) -> Result<Token<'a>, ParseError> {
+        if self
+            .peek_next()
+            .is_some_and(|token| token.kind.is_keyword())
+        {
+            return Err(self.error(ParseErrorKind::ReservedKeyword));
+        }
 
-            self.consume_1();
+        self.take_1(|token| token.kind == TokenKind::Ident)
+            .map_err(|e| e.expecting([TokenKind::Ident]))
+    }
+
+    pub(crate) fn tag(&mut self, token_kind: TokenKind) -> Result<Token<'a>, ParseError> {
+        if self.next_is(token_kind) {
+            Ok(self.consume_1().unwrap()) // Safe, as we checked peek_next() above
+        } else {
+            Err(self.unexpected([token_kind]))
         }
     }
 
-    pub(crate) fn extract_whitespace(&mut self) -> Result<(), String> {
-        self.take_while(|token| token.kind == TokenKind::Whitespace);
-        Ok(())
+    pub(crate) fn next_is(&mut self, token_kind: TokenKind) -> bool {
+        self.peek_next()
+            .is_some_and(|token| token.kind == token_kind)
     }
 
-    pub(crate) fn extract_identifier(&mut self) -> Result<Token, String> {
-        self.take_1(|token| token.kind == TokenKind::Ident)
+    /// An error of `kind` at the next token, or at the end of the input when there are none left
+    pub(crate) fn error(&mut self, kind: ParseErrorKind) -> ParseError {
+        let next = self.peek_next();
+        let span = next.map_or(self.previous, |token| token.span);
+        ParseError::new(kind, span).found(next)
     }
 
-    pub(crate) fn tag(&mut self, token_kind: TokenKind) -> Result<Token, String> {
-        let Some(actual) = self.peek_next() else {
-            return Err(format!("Expected \"{token_kind:?}\" but got \"None\""));
-        };
+    /// An error saying that one of `expected` should have come next
+    pub(crate) fn unexpected(
+        &mut self,
+        expected: impl IntoIterator<Item = TokenKind>,
+    ) -> ParseError {
+        self.error(ParseErrorKind::UnexpectedToken)
+            .expecting(expected)
+    }
 
-        if actual.kind == token_kind {
-            Ok(self.consume_1().unwrap()) // Safe, as we checked peek_next() above
-        } else {
-            Err(format!(
-                "Expected \"{token_kind:?}\" but got \"{:?}\"",
-                actual.kind
-            ))
+    /// Records `error` without stopping the parse
+    pub(crate) fn report(&mut self, error: ParseError) {
+        self.diagnostics.push(error);
+    }
+
+    /// Records `error` and skips to where parsing can pick up again: past the next ";", or up to
+    /// the "}" closing the current block or the next item. Returns the span from the error to the
+    /// end of what was skipped.
+    pub(crate) fn recover(&mut self, error: ParseError) -> Span {
+        let start = error.span;
+        self.report(error);
+
+        // Blocks opened while skipping are skipped whole, so their "}" and ";" don't count
+        let mut depth = 0;
+        while let Some(next) = self.peek_next() {
+            match next.kind {
+                TokenKind::Func => break,
+                TokenKind::CloseBrace if depth == 0 => break,
+                TokenKind::CloseBrace => depth -= 1,
+                TokenKind::OpenBrace => depth += 1,
+                TokenKind::Semi if depth == 0 => {
+                    self.consume_1();
+                    break;
+                }
+                _ => {}
+            }
+
+            self.consume_1();
         }
+
+        let end = self.previous.end.max(start.end);
+        Span::new(start.start, end, start.line, start.column)
     }
 
-    fn take_1(&mut self, pred: fn(&Token) -> bool) -> Result<Token, String> {
-        let Some(token) = self.peek_next() else {
-            return Err("Expected token, but found None".to_string());
-        };
+    /// Errors the lexer found in the tokens read so far
+    pub(crate) fn lex_errors(&self) -> &[LexError] {
+        self.ts.lex_errors()
+    }
 
-        if pred(&token) {
-            Ok(self.consume_1().unwrap()) // Shouldn't panic as we checked next in peek_next());
-        } else {
-            Err(format!("Unexpected token: \"{}\"", token))
+    /// Takes every error that was recovered from so far
+    pub(crate) fn take_diagnostics(&mut self) -> Vec<ParseError> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    fn take_1(&mut self, pred: fn(&Token) -> bool) -> Result<Token<'a>, ParseError> {
+        match self.peek_next() {
+            Some(token) if pred(&token) => Ok(self.consume_1().unwrap()), // Checked by peek_next()
+            _ => Err(self.error(ParseErrorKind::UnexpectedToken)),
         }
     }
 
-    fn consume_1(&mut self) -> Option<Token> {
-        self.ts.next()
+    fn consume_1(&mut self) -> Option<Token<'a>> {
+        let token = self.ts.next()?;
+        trace::consume(&token);
+        self.previous = token.span;
+        Some(token)
     }
 
-    pub(crate) fn peek_next(&mut self) -> Option<Token> {
+    pub(crate) fn peek_next(&mut self) -> Option<Token<'a>> {
         self.ts.peek_next()
     }
 
-    pub(crate) fn peek_n(&mut self, n: usize) -> Option<Token> {
+    pub(crate) fn peek_n(&mut self, n: usize) -> Option<Token<'a>> {
         self.ts.peek_n(n)
     }
 }
@@ -68,6 +125,10 @@ impl Pear<'_> {
 impl<'a> From<&'a str> for Pear<'a> {
     fn from(s: &'a str) -> Self {
         let ts = TokenStream::from(s);
-        Self { ts }
+        Self {
+            ts,
+            previous: Span::new(0, 0, 1, 1),
+            diagnostics: vec![],
+        }
     }
 }
@@ -0,0 +1,190 @@
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+
+use crate::lexer::{LexError, LexErrorKind, Span, Token, TokenKind};
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum ParseErrorKind {
+    /// The next token isn't one of `expected`
+    UnexpectedToken,
+    /// An expression was expected
+    MissingExpression,
+    /// A type was expected after ":" or "->"
+    MissingType,
+    /// A name was expected, e.g. after "func"
+    MissingName,
+    /// A keyword was used as a name
+    ReservedKeyword,
+    /// A "(" or "{" without its closing partner, `span` points at the opening one
+    Unclosed,
+    /// A literal that can't be turned into a value, the notes say why
+    InvalidLiteral,
+    /// A second parameter or item with the same name
+    Duplicate,
+    /// Found by the lexer, `found` holds the offending text as an `Unknown` token
+    Lexical(LexErrorKind),
+}
+
+/// The token a parser stopped at
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Found {
+    pub kind: TokenKind,
+    pub text: String,
+}
+
+impl Display for Found {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "\"{}\"", self.text)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    /// Where the error is, usually the token that was found instead of what was expected
+    pub span: Span,
+    /// Tokens that would have been accepted instead of `found`
+    pub expected: BTreeSet<TokenKind>,
+    /// `None` when the input ended early
+    pub found: Option<Found>,
+    pub notes: Vec<String>,
+}
+
+impl ParseError {
+    pub(crate) fn new(kind: ParseErrorKind, span: Span) -> Self {
+        Self {
+            kind,
+            span,
+            expected: BTreeSet::new(),
+            found: None,
+            notes: vec![],
+        }
+    }
+
+    /// `token` is a literal that can't be turned into a value, `why` says what's wrong with it
+    pub(crate) fn invalid_literal(token: Token, why: impl Into<String>) -> Self {
+        Self::new(ParseErrorKind::InvalidLiteral, token.span)
+            .found(Some(token))
+            .note(why)
+    }
+
+    pub(crate) fn expecting(mut self, kinds: impl IntoIterator<Item = TokenKind>) -> Self {
+        self.expected.extend(kinds);
+        self
+    }
+
+    pub(crate) fn found(mut self, token: Option<Token>) -> Self {
+        self.found = token.map(|token| Found {
+            kind: token.kind,
+            text: token.to_string(),
+        });
+        self
+    }
+
+    pub(crate) fn note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// `expected` as "\")\"", "\",\" or \")\"" and so on
+    fn expected_list(&self) -> String {
+        let kinds = self
+            .expected
+            .iter()
+            .map(|kind| match kind.has_fixed_text() {
+                true => format!("\"{kind}\""),
+                false => kind.to_string(),
+            })
+            .collect::<Vec<_>>();
+
+        match kinds.split_last() {
+            None => "nothing".to_string(),
+            Some((last, [])) => last.clone(),
+            Some((last, rest)) => format!("{} or {last}", rest.join(", ")),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let found = self
+            .found
+            .as_ref()
+            .map_or("end of input".to_string(), |found| found.to_string());
+
+        write!(f, "{}: ", self.span)?;
+        match self.kind {
+            ParseErrorKind::UnexpectedToken => {
+                write!(f, "Expected {}, found {found}", self.expected_list())
+            }
+            ParseErrorKind::MissingExpression => write!(f, "Expected an expression, found {found}"),
+            ParseErrorKind::MissingType => write!(f, "Expected a type, found {found}"),
+            ParseErrorKind::MissingName => write!(f, "Expected a name, found {found}"),
+            ParseErrorKind::ReservedKeyword => write!(
+                f,
+                "{found} is a reserved keyword and can't be used as an identifier"
+            ),
+            ParseErrorKind::Unclosed => write!(
+                f,
+                "Unclosed delimiter, expected {}, found {found}",
+                self.expected_list()
+            ),
+            ParseErrorKind::InvalidLiteral => write!(f, "Invalid literal {found}"),
+            ParseErrorKind::Duplicate => write!(f, "{found} is already defined"),
+            ParseErrorKind::Lexical(kind) => write!(f, "{kind}: {found}"),
+        }?;
+
+        for note in &self.notes {
+            write!(f, "\n  note: {note}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<LexError> for ParseError {
+    fn from(error: LexError) -> Self {
+        let found = Found {
+            kind: TokenKind::Unknown,
+            text: error.text,
+        };
+
+        Self {
+            found: Some(found),
+            ..Self::new(ParseErrorKind::Lexical(error.kind), error.span)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer::{Span, TokenIterator, TokenKind};
+    use crate::pear::error::{Found, ParseError, ParseErrorKind};
+
+    #[test]
+    fn display_parse_errors() {
+        let found = TokenIterator::from("b").next();
+        let error = ParseError::new(ParseErrorKind::UnexpectedToken, Span::new(4, 5, 1, 5))
+            .expecting([TokenKind::CloseParen, TokenKind::Comma])
+            .found(found);
+
+        assert_eq!(
+            error.found,
+            Some(Found {
+                kind: TokenKind::Ident,
+                text: "b".to_string()
+            })
+        );
+        assert_eq!(error.to_string(), r#"1:5: Expected ")" or ",", found "b""#);
+
+        let error = ParseError::new(ParseErrorKind::Unclosed, Span::new(0, 1, 1, 1))
+            .expecting([TokenKind::CloseBrace, TokenKind::Ident, TokenKind::Semi])
+            .note("the block starts here");
+        assert_eq!(
+            error.to_string(),
+            "1:1: Unclosed delimiter, expected identifier, \"}\" or \";\", found end of input\n  note: the block starts here"
+        );
+    }
+}
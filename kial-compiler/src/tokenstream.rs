@@ -1,69 +1,12 @@
-use crate::lexer::{RPNIterator, Token, TokenIterator, TokenKind};
+use crate::lexer::{LexError, Token, TokenIterator, TokenKind};
 use std::collections::VecDeque;
 
 pub(crate) struct TokenStream<'a> {
-    tokens: Box<dyn Iterator<Item = Token> + 'a>,
-    buffer: VecDeque<Token>,
+    tokens: TokenIterator<'a>,
+    buffer: VecDeque<Token<'a>>,
 }
 
 impl<'a> TokenStream<'a> {
-    fn starts_with_let(&mut self) -> bool {
-        let is_let = |index: usize, token: &Token| -> bool {
-            let first_is_let = TokenStream::nth_token_is(0, index, token, TokenKind::Let);
-            first_is_let
-        };
-
-        self.n_tokens_are(1, is_let)
-    }
-
-    fn is_decl(&mut self) -> bool {
-        let is_ident = |index: usize, token: &Token| -> bool {
-            let first_is_let = TokenStream::nth_token_is(0, index, token, TokenKind::Let);
-            let second_is_ident = TokenStream::nth_token_is(1, index, token, TokenKind::Ident);
-            let third_is_semi = TokenStream::nth_token_is(2, index, token, TokenKind::Semi);
-
-            first_is_let || second_is_ident || third_is_semi
-        };
-
-        self.n_tokens_are(2, is_ident)
-    }
-
-    fn is_init(&mut self) -> bool {
-        let is_init = |index: usize, token: &Token| -> bool {
-            let first_is_let = TokenStream::nth_token_is(0, index, token, TokenKind::Let);
-            let second_is_ident = TokenStream::nth_token_is(1, index, token, TokenKind::Ident);
-            let third_is_equals = TokenStream::nth_token_is(2, index, token, TokenKind::Equals);
-
-            first_is_let || second_is_ident || third_is_equals
-        };
-
-        self.n_tokens_are(3, is_init)
-    }
-
-    fn n_tokens_are(&mut self, n: usize, pred: fn(usize, &Token) -> bool) -> bool {
-        let tokens = self.read(n);
-
-        if tokens.len() < n {
-            return false;
-        }
-
-        for (index, token) in tokens.iter().enumerate() {
-            if index > (n - 1) {
-                return true;
-            }
-
-            if !pred(index, token) {
-                return false;
-            }
-        }
-
-        true
-    }
-
-    fn nth_token_is(n: usize, index: usize, token: &Token, expected: TokenKind) -> bool {
-        index == n && token.kind == expected
-    }
-
     fn advance(&mut self, n: usize) {
         if n <= self.buffer.len() {
             return;
@@ -71,8 +14,13 @@ impl<'a> TokenStream<'a> {
 
         let to_take = n - self.buffer.len();
 
-        for i in 0..to_take {
-            if let Some(token) = self.tokens.next() {
+        for _ in 0..to_take {
+            // TODO: Doc comments are dropped until the parser can attach them to declarations
+            let next = self
+                .tokens
+                .by_ref()
+                .find(|token| token.kind != TokenKind::DocComment);
+            if let Some(token) = next {
                 self.buffer.push_back(token);
             } else {
                 // Early return for efficiency
@@ -81,23 +29,29 @@ impl<'a> TokenStream<'a> {
         }
     }
 
-    pub(crate) fn read(&mut self, n: usize) -> VecDeque<Token> {
+    pub(crate) fn read(&mut self, n: usize) -> &VecDeque<Token<'a>> {
         self.advance(n);
 
-        self.buffer.clone()
+        &self.buffer
     }
 
-    pub(crate) fn peek_next(&mut self) -> Option<Token> {
-        self.read(1).pop_front()
+    /// Errors the lexer found in the tokens read so far
+    pub(crate) fn lex_errors(&self) -> &[LexError] {
+        self.tokens.errors()
     }
 
-    pub(crate) fn peek_n(&mut self, n: usize) -> Option<Token> {
-        self.read(n).pop_back()
+    pub(crate) fn peek_next(&mut self) -> Option<Token<'a>> {
+        self.peek_n(1)
+    }
+
+    /// The n-th upcoming token, counting from 1
+    pub(crate) fn peek_n(&mut self, n: usize) -> Option<Token<'a>> {
+        self.read(n).get(n.checked_sub(1)?).copied()
     }
 }
 
-impl Iterator for TokenStream<'_> {
-    type Item = Token;
+impl<'a> Iterator for TokenStream<'a> {
+    type Item = Token<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.advance(1);
@@ -108,7 +62,7 @@ impl Iterator for TokenStream<'_> {
 impl<'a> From<&'a str> for TokenStream<'a> {
     fn from(s: &'a str) -> Self {
         Self {
-            tokens: Box::new(RPNIterator::from_iter(TokenIterator::from(s))),
+            tokens: TokenIterator::from(s),
             buffer: VecDeque::new(),
         }
     }
@@ -116,8 +70,8 @@ impl<'a> From<&'a str> for TokenStream<'a> {
 
 #[cfg(test)]
 mod tests {
-    use crate::lexer::Token;
     use crate::lexer::TokenKind;
+    use crate::lexer::{Span, Token, TokenIterator};
     use crate::tokenstream::TokenStream;
 
     #[test]
@@ -127,8 +81,8 @@ mod tests {
             ts.peek_next(),
             Some(Token {
                 kind: TokenKind::Let,
-                val: "let".to_string(),
-                len: 3
+                val: "let",
+                span: Span::new(0, 3, 1, 1)
             })
         );
 
@@ -136,8 +90,8 @@ mod tests {
             ts.peek_n(2),
             Some(Token {
                 kind: TokenKind::Ident,
-                val: "i".to_string(),
-                len: 1
+                val: "i",
+                span: Span::new(4, 5, 1, 5)
             })
         );
 
@@ -145,8 +99,8 @@ mod tests {
             ts.next(),
             Some(Token {
                 kind: TokenKind::Let,
-                val: "let".to_string(),
-                len: 3
+                val: "let",
+                span: Span::new(0, 3, 1, 1)
             })
         );
 
@@ -154,161 +108,68 @@ mod tests {
             ts.next(),
             Some(Token {
                 kind: TokenKind::Ident,
-                val: "i".to_string(),
-                len: 1
+                val: "i",
+                span: Span::new(4, 5, 1, 5)
             })
         );
     }
 
     #[rustfmt::skip::macros(assert_eq)]
     #[test]
-    fn reverse_polish_notation_iterator_test() {
+    fn iterator_keeps_source_order() {
         let mut ts = TokenStream::from("let i = 10 + 20 + 30;");
-        assert_eq!(ts.next(), Some(Token { kind: TokenKind::Let, val: "let".to_string(), len: 3 }));
+        assert_eq!(ts.next(), Some(Token { kind: TokenKind::Let, val: "let", span: Span::new(0, 3, 1, 1) }));
 
-        assert_eq!(ts.next(), Some(Token { kind: TokenKind::Ident, val: "i".to_string(), len: 1 }));
+        assert_eq!(ts.next(), Some(Token { kind: TokenKind::Ident, val: "i", span: Span::new(4, 5, 1, 5) }));
 
-        assert_eq!(ts.next(), Some(Token { kind: TokenKind::Equals, val: "".to_string(), len: 1 }));
+        assert_eq!(ts.next(), Some(Token { kind: TokenKind::Equals, val: "=", span: Span::new(6, 7, 1, 7) }));
 
-        assert_eq!(ts.next(), Some(Token { kind: TokenKind::NumericLiteral, val: "10".to_string(), len: 2 }));
+        assert_eq!(ts.next(), Some(Token { kind: TokenKind::NumericLiteral, val: "10", span: Span::new(8, 10, 1, 9) }));
 
-        assert_eq!(ts.next(), Some(Token { kind: TokenKind::NumericLiteral, val: "20".to_string(), len: 2 }));
+        assert_eq!(ts.next(), Some(Token { kind: TokenKind::Plus, val: "+", span: Span::new(11, 12, 1, 12) }));
 
-        assert_eq!(ts.next(), Some(Token { kind: TokenKind::NumericLiteral, val: "30".to_string(), len: 2 }));
+        assert_eq!(ts.next(), Some(Token { kind: TokenKind::NumericLiteral, val: "20", span: Span::new(13, 15, 1, 14) }));
 
-        assert_eq!(ts.next(), Some(Token { kind: TokenKind::Plus, val: "".to_string(), len: 1 }));
+        assert_eq!(ts.next(), Some(Token { kind: TokenKind::Plus, val: "+", span: Span::new(16, 17, 1, 17) }));
 
-        assert_eq!(ts.next(), Some(Token { kind: TokenKind::Plus, val: "".to_string(), len: 1 }));
+        assert_eq!(ts.next(), Some(Token { kind: TokenKind::NumericLiteral, val: "30", span: Span::new(18, 20, 1, 19) }));
 
-        assert_eq!(ts.next(), Some(Token { kind: TokenKind::Semi, val: "".to_string(), len: 1 }));
+        assert_eq!(ts.next(), Some(Token { kind: TokenKind::Semi, val: ";", span: Span::new(20, 21, 1, 21) }));
 
         assert_eq!(ts.next(), None);
     }
 
-    #[ignore] // TODO: Just for debugging
-    #[test]
-    fn reverse_polish_notation() {
-        let mut ts = TokenStream::from("let a = 10 + 5 - 8;");
-        let tokens = ts.next().unwrap();
-    
-        println!("{tokens:?}")
-    }
-
-    #[test]
-    fn check_is_init() {
-        let mut ts = TokenStream::from("let i = 10 + 20 + 30;");
-        assert!(ts.is_init());
-    }
-
-    #[test]
-    fn check_is_not_init() {
-        let mut ts = TokenStream::from("let i;");
-        assert!(!ts.is_init());
-    }
-
-    #[test]
-    fn check_starts_with_let() {
-        let mut ts = TokenStream::from("let a");
-        assert!(ts.starts_with_let());
-        assert!(!ts.is_init())
-    }
-
-    #[test]
-    fn check_doesnt_start_with_let() {
-        let mut ts = TokenStream::from("hello");
-        assert!(!ts.starts_with_let());
-
-        assert!(!ts.is_decl())
-    }
-
-    #[test]
-    fn check_is_decl() {
-        let mut ts = TokenStream::from("let a;");
-        assert!(ts.is_decl());
-
-        assert!(ts.starts_with_let());
-
-        assert!(!ts.is_init());
-    }
-
-    #[test]
-    fn check_is_not_decl() {
-        let mut ts = TokenStream::from("a");
-        assert!(!ts.is_decl());
-    }
-
     #[test]
     fn read_n_from_token_stream() {
-        let mut ts = TokenStream::from("a b c d;");
+        let text = "a b c d;";
+        let mut ts = TokenStream::from(text);
+        let first_n = |n: usize| TokenIterator::from(text).take(n).collect::<Vec<Token>>();
 
         let tokens = ts.read(2);
         assert_eq!(tokens.len(), 2);
-        assert_eq!(
-            tokens,
-            vec![Token::try_from("a").unwrap(), Token::try_from("b").unwrap()]
-        );
+        assert_eq!(*tokens, first_n(2));
 
         let tokens = ts.read(4);
         assert_eq!(tokens.len(), 4);
-        assert_eq!(
-            tokens,
-            vec![
-                Token::try_from("a").unwrap(),
-                Token::try_from("b").unwrap(),
-                Token::try_from("c").unwrap(),
-                Token::try_from("d").unwrap()
-            ]
-        );
+        assert_eq!(*tokens, first_n(4));
 
         let tokens = ts.read(2);
         assert_eq!(tokens.len(), 4);
-        assert_eq!(
-            tokens,
-            vec![
-                Token::try_from("a").unwrap(),
-                Token::try_from("b").unwrap(),
-                Token::try_from("c").unwrap(),
-                Token::try_from("d").unwrap()
-            ]
-        );
+        assert_eq!(*tokens, first_n(4));
 
         let tokens = ts.read(5);
         assert_eq!(tokens.len(), 5);
-        assert_eq!(
-            tokens,
-            vec![
-                Token::try_from("a").unwrap(),
-                Token::try_from("b").unwrap(),
-                Token::try_from("c").unwrap(),
-                Token::try_from("d").unwrap(),
-                Token::try_from(";").unwrap()
-            ]
-        );
+        assert_eq!(*tokens, first_n(5));
+        assert_eq!(tokens[0], Token::try_from("a").unwrap());
+        assert_eq!(tokens[4].kind, TokenKind::Semi);
+        assert_eq!(tokens[4].span, Span::new(7, 8, 1, 8));
 
         let tokens = ts.read(0);
         assert_eq!(tokens.len(), 5);
-        assert_eq!(
-            tokens,
-            vec![
-                Token::try_from("a").unwrap(),
-                Token::try_from("b").unwrap(),
-                Token::try_from("c").unwrap(),
-                Token::try_from("d").unwrap(),
-                Token::try_from(";").unwrap()
-            ]
-        );
+        assert_eq!(*tokens, first_n(5));
 
         let tokens = ts.read(100);
         assert_eq!(tokens.len(), 5);
-        assert_eq!(
-            tokens,
-            vec![
-                Token::try_from("a").unwrap(),
-                Token::try_from("b").unwrap(),
-                Token::try_from("c").unwrap(),
-                Token::try_from("d").unwrap(),
-                Token::try_from(";").unwrap()
-            ]
-        );
+        assert_eq!(*tokens, first_n(5));
     }
 }
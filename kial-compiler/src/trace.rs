@@ -0,0 +1,231 @@
+//! Records what the lexer and the parser do with each token, for debugging the compiler itself.
+//! Only compiled in with the `trace` feature, the hooks do nothing otherwise.
+#![cfg_attr(not(feature = "trace"), allow(unused_variables))]
+
+use crate::lexer::Token;
+use crate::pear::Pear;
+
+#[cfg(feature = "trace")]
+pub use recorder::{collect, render, trace_parse, Event};
+
+/// Marks the parser as being inside of a node until dropped
+pub(crate) struct Node {
+    #[cfg(feature = "trace")]
+    name: &'static str,
+}
+
+/// The parser started on `name`, which begins at the next token of `pear`
+pub(crate) fn enter(name: &'static str, pear: &mut Pear) -> Node {
+    #[cfg(feature = "trace")]
+    recorder::enter(name, pear.peek_next());
+
+    Node {
+        #[cfg(feature = "trace")]
+        name,
+    }
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        #[cfg(feature = "trace")]
+        recorder::exit(self.name);
+    }
+}
+
+/// The lexer produced `token`
+pub(crate) fn lex(token: &Token) {
+    #[cfg(feature = "trace")]
+    recorder::record(Event::Lex {
+        token: token.to_string(),
+        span: token.span,
+    });
+}
+
+/// The parser consumed `token`
+pub(crate) fn consume(token: &Token) {
+    #[cfg(feature = "trace")]
+    recorder::consume(token);
+}
+
+#[cfg(feature = "trace")]
+mod recorder {
+    use crate::ast::statement::Stmt;
+    use crate::lexer::{Span, Token};
+    use crate::pear::Pear;
+    use std::cell::RefCell;
+    use std::fmt::Write;
+
+    #[derive(Debug, PartialEq, Clone)]
+    pub enum Event {
+        /// The lexer produced `token`
+        Lex { token: String, span: Span },
+        /// The parser started on `node`, `span` is the next token if there is one
+        Enter {
+            node: &'static str,
+            span: Option<Span>,
+        },
+        /// The parser consumed `token`
+        Consume { token: String, span: Span },
+        /// The parser finished `node`, `span` covers the tokens consumed since it was entered
+        Exit {
+            node: &'static str,
+            span: Option<Span>,
+        },
+    }
+
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<Event>,
+        /// How many tokens had been consumed when each open node was entered
+        open: Vec<usize>,
+        consumed: Vec<Span>,
+    }
+
+    thread_local! {
+        static RECORDER: RefCell<Option<Recorder>> = const { RefCell::new(None) };
+    }
+
+    /// Runs `f`, returning everything recorded on this thread while it ran
+    pub fn collect<T>(f: impl FnOnce() -> T) -> (T, Vec<Event>) {
+        let outer = RECORDER.with(|r| r.replace(Some(Recorder::default())));
+        let result = f();
+        let recorder = RECORDER.with(|r| r.replace(outer)).unwrap_or_default();
+        (result, recorder.events)
+    }
+
+    fn with(f: impl FnOnce(&mut Recorder)) {
+        RECORDER.with(|r| {
+            if let Some(recorder) = r.borrow_mut().as_mut() {
+                f(recorder)
+            }
+        });
+    }
+
+    pub(super) fn record(event: Event) {
+        with(|r| r.events.push(event));
+    }
+
+    pub(super) fn enter(node: &'static str, next: Option<Token>) {
+        with(|r| {
+            r.open.push(r.consumed.len());
+            r.events.push(Event::Enter {
+                node,
+                span: next.map(|t| t.span),
+            });
+        });
+    }
+
+    pub(super) fn consume(token: &Token) {
+        with(|r| {
+            r.consumed.push(token.span);
+            r.events.push(Event::Consume {
+                token: token.to_string(),
+                span: token.span,
+            });
+        });
+    }
+
+    pub(super) fn exit(node: &'static str) {
+        with(|r| {
+            let first = r.open.pop().unwrap_or_default();
+            let span = r.consumed.get(first).map(|start| {
+                let end = r.consumed.last().unwrap(); // There's at least `start`
+                Span::new(start.start, end.end, start.line, start.column)
+            });
+            r.events.push(Event::Exit { node, span });
+        });
+    }
+
+    /// Renders the parser's events as an indented tree of nodes and the tokens they consumed.
+    /// Lexer events are left out.
+    pub fn render(events: &[Event]) -> String {
+        let mut out = String::new();
+        let mut depth = 0;
+        for event in events {
+            let indent = "  ".repeat(depth);
+            match event {
+                Event::Enter { node, span } => {
+                    let at = span.map_or("EOF".to_string(), |s| s.to_string());
+                    writeln!(out, "{indent}{node} @ {at}").unwrap();
+                    depth += 1;
+                }
+                Event::Consume { token, span } => {
+                    writeln!(out, "{indent}{token:?} @ {span}").unwrap();
+                }
+                Event::Exit { .. } => depth = depth.saturating_sub(1),
+                Event::Lex { .. } => {}
+            }
+        }
+
+        out
+    }
+
+    /// Parses `src` as a list of statements and renders what the parser did
+    pub fn trace_parse(src: &str) -> String {
+        let (_, events) = collect(|| {
+            let mut pear = Pear::from(src);
+            while pear.peek_next().is_some() && Stmt::try_from(&mut pear).is_ok() {}
+        });
+
+        render(&events)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use crate::ast::expression::Expr;
+        use crate::lexer::{Span, TokenIterator};
+        use crate::pear::Pear;
+        use crate::trace::{collect, render, trace_parse, Event};
+
+        #[test]
+        fn collect_only_while_running() {
+            let (_, events) = collect(|| TokenIterator::from("a + 1").count());
+            assert_eq!(events.len(), 3);
+            assert_eq!(
+                events[1],
+                Event::Lex {
+                    token: "+".to_string(),
+                    span: Span::new(2, 3, 1, 3)
+                }
+            );
+
+            // Nothing is recorded outside of collect()
+            TokenIterator::from("a + 1").count();
+            let (_, events) = collect(|| ());
+            assert_eq!(events, vec![]);
+        }
+
+        #[test]
+        fn exit_spans_cover_consumed_tokens() {
+            let (_, events) = collect(|| Expr::try_from(&mut Pear::from("(1 +\n 2)")));
+            let exits = events
+                .iter()
+                .filter_map(|event| match event {
+                    Event::Exit { node, span } => Some((*node, *span)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+
+            assert_eq!(exits.last(), Some(&("Expr", Some(Span::new(0, 8, 1, 1)))));
+            assert!(exits.contains(&("Literal", Some(Span::new(6, 7, 2, 2)))));
+        }
+
+        #[test]
+        fn render_parse_tree() {
+            let (_, events) = collect(|| Expr::try_from(&mut Pear::from("-a")));
+            assert_eq!(
+                render(&events),
+                "\
+Expr @ 1:1
+  Unary @ 1:1
+    \"-\" @ 1:1
+    Expr @ 1:2
+      BindingUsage @ 1:2
+        Ident @ 1:2
+          \"a\" @ 1:2
+"
+            );
+            assert!(trace_parse("let a = 1;").starts_with("Stmt @ 1:1\n"));
+        }
+    }
+}
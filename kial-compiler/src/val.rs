@@ -0,0 +1,27 @@
+use std::fmt::{Display, Formatter};
+
+/// What evaluating an expression produces
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum Val {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Str(String),
+    Char(char),
+    Bytes(Vec<u8>),
+    Unit,
+}
+
+impl Display for Val {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Val::Int(int) => write!(f, "{int}"),
+            Val::Float(float) => write!(f, "{float}"),
+            Val::Bool(bool) => write!(f, "{bool}"),
+            Val::Str(str) => write!(f, "{str}"),
+            Val::Char(c) => write!(f, "{c}"),
+            Val::Bytes(bytes) => write!(f, "b\"{}\"", bytes.escape_ascii()),
+            Val::Unit => write!(f, "()"),
+        }
+    }
+}
@@ -2,6 +2,13 @@ use old_kial_compiler::env::Env;
 use std::io::Write;
 
 fn main() {
+    // Prints the new parser's trace for every line before evaluating it
+    let trace_parse = std::env::args().skip(1).any(|arg| arg == "--trace-parse");
+    if trace_parse && !cfg!(feature = "trace") {
+        eprintln!("--trace-parse needs the \"trace\" feature, e.g. cargo run --features trace");
+        std::process::exit(1);
+    }
+
     let mut env = Env::default();
 
     let stdin = std::io::stdin();
@@ -9,12 +16,17 @@ fn main() {
         let mut input = String::new();
         print!("> ");
         std::io::stdout().flush().unwrap();
-        if let Ok(_) = stdin.read_line(&mut input) {
+        if stdin.read_line(&mut input).is_ok() {
             let input = input.trim();
             if input.is_empty() {
                 continue;
             }
 
+            #[cfg(feature = "trace")]
+            if trace_parse {
+                print!("{}", kial_compiler::trace::trace_parse(input));
+            }
+
             match old_kial_compiler::parse(input) {
                 Ok(parse) => match parse.eval(&mut env) {
                     Ok(val) => println!("{val}"),
@@ -17,12 +17,7 @@ impl BindingUsage {
     pub fn parse(s: &str) -> Result<(&str, Self), String> {
         let (s, _) = utils::extract_whitespace(s);
         let (s, name) = utils::extract_ident(s)?;
-        Ok((
-            s,
-            Self {
-                name: name.to_string(),
-            },
-        ))
+        Ok((s, Self::new(name)))
     }
 
     pub fn eval(&self, env: &Env) -> Result<Val, String> {
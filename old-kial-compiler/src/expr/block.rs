@@ -31,7 +31,7 @@ impl Block {
         let s = utils::tag("}", s)?;
         let (s, _) = utils::extract_whitespace(s);
 
-        Ok((s, Self { stmts }))
+        Ok((s, Self::new(stmts)))
     }
 
     pub fn eval(&self, env: &Env) -> Result<Val, String> {
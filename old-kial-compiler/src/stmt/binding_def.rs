@@ -37,13 +37,7 @@ impl BindingDef {
         let s = utils::tag(";", s)?;
         let (s, _) = utils::extract_whitespace(s);
 
-        Ok((
-            s,
-            Self {
-                name: name.to_string(),
-                val,
-            },
-        ))
+        Ok((s, Self::new(name, val)))
     }
 
     pub(crate) fn eval(&self, env: &mut Env) -> Result<Val, String> {
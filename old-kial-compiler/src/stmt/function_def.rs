@@ -52,14 +52,7 @@ impl FunctionDef {
         let (s, body) = Block::parse(s)?;
         let (s, _) = utils::extract_whitespace(s);
 
-        Ok((
-            s,
-            Self {
-                name: name.to_string(),
-                args: args,
-                body,
-            },
-        ))
+        Ok((s, Self::new(name, args, body)))
     }
 
     pub(crate) fn eval(&self, env: &mut Env) -> Result<Val, String> {